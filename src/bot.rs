@@ -0,0 +1,240 @@
+use crate::game::{Direction, Game, Point};
+use serde::Serialize;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError, TrySendError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// State of the game sent to the bot as a single JSON line every tick.
+#[derive(Serialize)]
+struct BotState<'a> {
+    width: i32,
+    height: i32,
+    /// Body of the snake starting from the head.
+    snake: Vec<&'a Point>,
+    /// Bodies of the other snakes alive, each starting from the head.
+    others: Vec<Vec<&'a Point>>,
+    apple: &'a Point,
+    /// Positions of the hazards.
+    hazards: Vec<&'a Point>,
+    direction: &'a Direction,
+    tick: u64,
+}
+
+/// An external program controlling the snake.
+///
+/// Every tick the bot receives the state of the game on its stdin and
+/// replies with one line on its stdout containing the direction to move in,
+/// either as a plain word (`left`) or as a JSON string (`"left"`). If the bot
+/// doesn't reply in time, or the reply can't be parsed, the snake keeps its
+/// current direction and a warning is recorded. Every state has to be answered
+/// with exactly one line, and replies are matched to the states in order, so
+/// replies that arrive after the timeout are discarded.
+///
+/// The state is written from another thread, so a bot that stops reading its
+/// stdin can't block the game. While the previous state is still waiting to be
+/// written the bot is treated as timed out.
+pub struct Bot {
+    child: Child,
+    states: mpsc::SyncSender<String>,
+    replies: mpsc::Receiver<String>,
+    timeout: Duration,
+    /// Number of states sent to the bot.
+    sent: u64,
+    /// Number of replies read from the bot.
+    answered: u64,
+    pub warning: Option<String>,
+}
+
+impl Bot {
+    pub fn spawn(command: &str, timeout: Duration) -> io::Result<Self> {
+        let args = split_command(command);
+        let (program, args) = args
+            .split_first()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "bot command is empty"))?;
+
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        let mut stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let (tx, replies) = mpsc::channel();
+        let (states, rx) = mpsc::sync_channel::<String>(1);
+
+        thread::spawn(move || {
+            for line in rx {
+                if stdin
+                    .write_all(line.as_bytes())
+                    .and_then(|_| stdin.flush())
+                    .is_err()
+                {
+                    return;
+                }
+            }
+        });
+
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if tx.send(line).is_err() {
+                    return;
+                }
+            }
+        });
+
+        Ok(Bot {
+            child,
+            states,
+            replies,
+            timeout,
+            sent: 0,
+            answered: 0,
+            warning: None,
+        })
+    }
+
+    pub fn next_direction(&mut self, game: &Game) -> Direction {
+        let state = BotState {
+            width: game.board.0,
+            height: game.board.1,
+            snake: game.snake().iter().rev().collect(),
+            others: game
+                .snakes
+                .iter()
+                .skip(1)
+                .filter(|snake| snake.alive)
+                .map(|snake| snake.body.iter().rev().collect())
+                .collect(),
+            apple: &game.apple,
+            hazards: game.hazards.iter().map(|h| &h.position).collect(),
+            direction: game.dir(),
+            tick: game.ticks,
+        };
+
+        let mut line = serde_json::to_string(&state).unwrap();
+        line.push('\n');
+
+        match self.states.try_send(line) {
+            Ok(()) => self.sent += 1,
+            Err(TrySendError::Full(_)) => {
+                return self.warn(game, "timed out, the bot isn't reading".to_string())
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                return self.warn(game, "couldn't send the state".to_string())
+            }
+        }
+
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+
+            return match self.replies.recv_timeout(timeout) {
+                Ok(reply) => {
+                    self.answered += 1;
+                    // a late reply to an earlier state
+                    if self.answered < self.sent {
+                        continue;
+                    }
+
+                    match parse_direction(&reply) {
+                        Some(dir) => {
+                            self.warning = None;
+                            dir
+                        }
+                        None => self.warn(game, format!("invalid reply {:?}", reply)),
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => self.warn(game, "timed out".to_string()),
+                Err(RecvTimeoutError::Disconnected) => self.warn(game, "exited".to_string()),
+            };
+        }
+    }
+
+    fn warn(&mut self, game: &Game, warning: String) -> Direction {
+        self.warning = Some(format!("tick {}: {}", game.ticks, warning));
+        game.dir().clone()
+    }
+}
+
+impl Drop for Bot {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn parse_direction(reply: &str) -> Option<Direction> {
+    let reply = reply.trim();
+    let reply = serde_json::from_str::<String>(reply).unwrap_or_else(|_| reply.to_string());
+
+    match reply.to_lowercase().as_str() {
+        "left" => Some(Direction::Left),
+        "right" => Some(Direction::Right),
+        "up" => Some(Direction::Up),
+        "down" => Some(Direction::Down),
+        _ => None,
+    }
+}
+
+/// Splits a command line on whitespace, keeping quoted parts together.
+fn split_command(command: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quote = None;
+
+    for c in command.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => current.push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            None if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            None => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+
+    if in_arg {
+        args.push(current);
+    }
+
+    args
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::game::Options;
+
+    #[test]
+    fn late_reply_is_ignored() {
+        // answers the first state late and every other one right away
+        let mut bot = Bot::spawn(
+            "sh -c 'read state; sleep 0.3; echo up; while read state; do echo down; done'",
+            Duration::from_millis(200),
+        )
+        .unwrap();
+        let game = Game::new(&Options::default());
+
+        assert_eq!(bot.next_direction(&game), Direction::Right);
+        assert!(bot.warning.is_some());
+
+        // the reply to the first state arrives while waiting for this one
+        assert_eq!(bot.next_direction(&game), Direction::Down);
+        assert!(bot.warning.is_none());
+    }
+}
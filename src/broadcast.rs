@@ -0,0 +1,444 @@
+use crate::event::{Event, EventHandle};
+use crate::game::{Game, Point};
+use crate::interface::{
+    draw_apple, draw_board, draw_hazards, draw_items, draw_level, draw_snake, new_grid,
+    restore_terminal, setup_terminal, snake_colors,
+};
+use crate::item::Item;
+use crossterm::event::KeyCode;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::error::Error;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tui::style::{Color, Style};
+use tui::text::{Span, Spans};
+
+/// Messages a spectator can fall behind by before it is dropped.
+const SPECTATOR_BACKLOG: usize = 64;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Running,
+    Paused,
+    GameOver,
+    Won,
+    Completed,
+}
+
+impl From<&Game> for Status {
+    fn from(game: &Game) -> Self {
+        if game.is_running() {
+            Status::Running
+        } else if game.is_game_over() {
+            Status::GameOver
+        } else if game.is_won() {
+            Status::Won
+        } else if game.is_completed() {
+            Status::Completed
+        } else {
+            Status::Paused
+        }
+    }
+}
+
+/// A snake other than the player's.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct RivalState {
+    pub id: usize,
+    /// Body of the snake from its tail to its head.
+    pub snake: Vec<Point>,
+    pub alive: bool,
+}
+
+/// Messages sent to spectators. A spectator first receives a snapshot of the
+/// whole board and then only the changes since the previous message.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SpectatorMessage {
+    Snapshot {
+        width: i32,
+        height: i32,
+        /// Body of the snake from its tail to its head.
+        snake: Vec<Point>,
+        apple: Point,
+        #[serde(default)]
+        walls: Vec<Point>,
+        #[serde(default)]
+        portals: Vec<(Point, Point)>,
+        #[serde(default)]
+        hazards: Vec<Point>,
+        #[serde(default)]
+        rivals: Vec<RivalState>,
+        #[serde(default)]
+        items: Vec<Item>,
+        status: Status,
+        tick: u64,
+    },
+    Delta {
+        /// Segments added in front of the head, oldest first.
+        grown: Vec<Point>,
+        /// Number of segments removed from the tail.
+        shrunk: usize,
+        apple: Option<Point>,
+        /// Every wall, sent whenever walls were added.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        walls: Option<Vec<Point>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        hazards: Option<Vec<Point>>,
+        /// Every other snake, sent whenever one of them changed.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        rivals: Option<Vec<RivalState>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        items: Option<Vec<Item>>,
+        status: Option<Status>,
+        tick: u64,
+    },
+}
+
+/// Publishes the state of a running game to everyone connected to it.
+///
+/// Every spectator is written to from its own thread, so a slow spectator
+/// can't hold up the game. A spectator that falls too far behind is dropped.
+pub struct Broadcaster {
+    pending: Arc<Mutex<Vec<SyncSender<String>>>>,
+    spectators: Vec<SyncSender<String>>,
+    snake: VecDeque<Point>,
+    apple: Point,
+    walls: Vec<Point>,
+    hazards: Vec<Point>,
+    rivals: Vec<RivalState>,
+    items: Vec<Item>,
+    status: Status,
+    tick: u64,
+}
+
+impl Broadcaster {
+    pub fn bind(addr: &str) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let pending = Arc::new(Mutex::new(Vec::new()));
+        let incoming = Arc::clone(&pending);
+
+        thread::spawn(move || {
+            for stream in listener.incoming().filter_map(Result::ok) {
+                let _ = stream.set_write_timeout(Some(Duration::from_secs(1)));
+                incoming.lock().unwrap().push(spectator(stream));
+            }
+        });
+
+        Ok(Broadcaster {
+            pending,
+            spectators: Vec::new(),
+            snake: VecDeque::new(),
+            apple: Point::new(0, 0),
+            walls: Vec::new(),
+            hazards: Vec::new(),
+            rivals: Vec::new(),
+            items: Vec::new(),
+            status: Status::Running,
+            tick: 0,
+        })
+    }
+
+    pub fn publish(&mut self, game: &Game) {
+        let status = Status::from(game);
+        let delta = self.delta(game, status);
+
+        if let Some(delta) = delta {
+            let line = to_line(&delta);
+            self.spectators
+                .retain(|spectator| spectator.try_send(line.clone()).is_ok());
+        }
+
+        let pending = std::mem::take(&mut *self.pending.lock().unwrap());
+        if !pending.is_empty() {
+            let line = to_line(&snapshot(game, status));
+
+            for spectator in pending {
+                if spectator.try_send(line.clone()).is_ok() {
+                    self.spectators.push(spectator);
+                }
+            }
+        }
+
+        self.snake.clone_from(game.snake());
+        self.apple.clone_from(&game.apple);
+        self.walls.clone_from(&game.walls);
+        self.hazards = hazard_positions(game);
+        self.rivals = rival_states(game);
+        self.items.clone_from(&game.items);
+        self.status = status;
+        self.tick = game.ticks;
+    }
+
+    /// Returns the changes since the last published state, a snapshot if the
+    /// game was restarted, or `None` if nothing changed.
+    fn delta(&self, game: &Game, status: Status) -> Option<SpectatorMessage> {
+        let apple = (game.apple != self.apple).then(|| game.apple.clone());
+        let changed_status = (status != self.status).then_some(status);
+        let walls = (game.walls != self.walls).then(|| game.walls.clone());
+        let hazards = Some(hazard_positions(game)).filter(|hazards| *hazards != self.hazards);
+        let rivals = Some(rival_states(game)).filter(|rivals| *rivals != self.rivals);
+        let items = (game.items != self.items).then(|| game.items.clone());
+
+        if game.ticks < self.tick {
+            return Some(snapshot(game, status));
+        }
+
+        let snake = game.snake();
+        let grown = (0..=snake.len()).find(|grown| {
+            let kept = snake.len() - grown;
+            kept <= self.snake.len()
+                && snake
+                    .range(..kept)
+                    .eq(self.snake.range(self.snake.len() - kept..))
+        });
+
+        match grown {
+            Some(0)
+                if apple.is_none()
+                    && walls.is_none()
+                    && hazards.is_none()
+                    && rivals.is_none()
+                    && items.is_none()
+                    && changed_status.is_none()
+                    && game.ticks == self.tick =>
+            {
+                None
+            }
+            Some(grown) => Some(SpectatorMessage::Delta {
+                grown: snake.range(snake.len() - grown..).cloned().collect(),
+                shrunk: self.snake.len() - (snake.len() - grown),
+                apple,
+                walls,
+                hazards,
+                rivals,
+                items,
+                status: changed_status,
+                tick: game.ticks,
+            }),
+            None => Some(snapshot(game, status)),
+        }
+    }
+}
+
+/// Starts the thread writing the lines sent to the spectator to its stream.
+/// The thread ends once the spectator is dropped or the stream fails.
+fn spectator(mut stream: TcpStream) -> SyncSender<String> {
+    let (tx, rx) = mpsc::sync_channel::<String>(SPECTATOR_BACKLOG);
+
+    thread::spawn(move || {
+        for line in rx {
+            if stream.write_all(line.as_bytes()).is_err() {
+                return;
+            }
+        }
+    });
+
+    tx
+}
+
+/// The message as a single JSON line.
+fn to_line(message: &SpectatorMessage) -> String {
+    let mut line = serde_json::to_string(message).unwrap();
+    line.push('\n');
+    line
+}
+
+fn hazard_positions(game: &Game) -> Vec<Point> {
+    game.hazards.iter().map(|h| h.position.clone()).collect()
+}
+
+fn rival_states(game: &Game) -> Vec<RivalState> {
+    game.snakes
+        .iter()
+        .skip(1)
+        .map(|snake| RivalState {
+            id: snake.id,
+            snake: snake.body.iter().cloned().collect(),
+            alive: snake.alive,
+        })
+        .collect()
+}
+
+fn snapshot(game: &Game, status: Status) -> SpectatorMessage {
+    SpectatorMessage::Snapshot {
+        width: game.board.0,
+        height: game.board.1,
+        snake: game.snake().iter().cloned().collect(),
+        apple: game.apple.clone(),
+        walls: game.walls.clone(),
+        portals: game.portals.clone(),
+        hazards: hazard_positions(game),
+        rivals: rival_states(game),
+        items: game.items.clone(),
+        status,
+        tick: game.ticks,
+    }
+}
+
+/// Watches a game published with `--broadcast` without being able to control
+/// it.
+pub fn watch(addr: &str) -> Result<(), Box<dyn Error>> {
+    let stream = TcpStream::connect(addr)?;
+    let mut terminal = setup_terminal()?;
+    let events = EventHandle::without_ticks();
+    let tx = events.sender();
+
+    thread::spawn(move || {
+        for line in BufReader::new(stream).lines().map_while(Result::ok) {
+            if tx.send(Event::Message(line)).is_err() {
+                return;
+            }
+        }
+        let _ = tx.send(Event::Disconnected);
+    });
+
+    let mut board = None;
+    let mut snake = VecDeque::new();
+    let mut apple = Point::new(0, 0);
+    let mut walls = Vec::new();
+    let mut portals = Vec::new();
+    let mut hazards = Vec::new();
+    let mut rivals: Vec<RivalState> = Vec::new();
+    let mut items = Vec::new();
+    let mut status = Status::Running;
+    let mut tick = 0;
+    let mut connected = true;
+
+    loop {
+        if let Some(board) = board {
+            let mut grid = new_grid(board);
+            draw_level(&mut grid, &walls, &portals);
+            draw_apple(&mut grid, &apple);
+            draw_items(&mut grid, &items);
+            draw_snake(&mut grid, snake.iter(), Color::Blue, Color::Yellow);
+            for rival in rivals.iter().filter(|rival| rival.alive) {
+                let (head_color, body_color) = snake_colors(rival.id);
+                draw_snake(&mut grid, rival.snake.iter(), head_color, body_color);
+            }
+            draw_hazards(&mut grid, &hazards);
+
+            let info = if !connected {
+                Span::styled("Broadcast ended", Style::default().fg(Color::Red))
+            } else {
+                Span::raw(format!("Watching, tick {}", tick))
+            };
+            let status_line = Spans::from(vec![info, Span::raw(", quit: q")]);
+
+            let border_color = match status {
+                _ if !connected => Color::Red,
+                Status::Running => Color::Green,
+                Status::Paused => Color::Yellow,
+                Status::GameOver => Color::Red,
+                Status::Won | Status::Completed => Color::Cyan,
+            };
+
+            terminal.draw(|f| {
+                draw_board(f, grid, border_color, status_line);
+            })?;
+        }
+
+        match events.next()? {
+            Event::Input(key) if key.code == KeyCode::Char('q') => break,
+            Event::Message(line) => match serde_json::from_str(&line) {
+                Ok(SpectatorMessage::Snapshot {
+                    width,
+                    height,
+                    snake: body,
+                    apple: new_apple,
+                    walls: new_walls,
+                    portals: new_portals,
+                    hazards: new_hazards,
+                    rivals: new_rivals,
+                    items: new_items,
+                    status: new_status,
+                    tick: new_tick,
+                }) => {
+                    board = Some((width, height));
+                    snake = body.into();
+                    apple = new_apple;
+                    walls = new_walls;
+                    portals = new_portals;
+                    hazards = new_hazards;
+                    rivals = new_rivals;
+                    items = new_items;
+                    status = new_status;
+                    tick = new_tick;
+                }
+                Ok(SpectatorMessage::Delta {
+                    grown,
+                    shrunk,
+                    apple: new_apple,
+                    walls: new_walls,
+                    hazards: new_hazards,
+                    rivals: new_rivals,
+                    items: new_items,
+                    status: new_status,
+                    tick: new_tick,
+                }) => {
+                    walls = new_walls.unwrap_or(walls);
+                    hazards = new_hazards.unwrap_or(hazards);
+                    rivals = new_rivals.unwrap_or(rivals);
+                    items = new_items.unwrap_or(items);
+                    snake.drain(..shrunk.min(snake.len()));
+                    snake.extend(grown);
+                    apple = new_apple.unwrap_or(apple);
+                    status = new_status.unwrap_or(status);
+                    tick = new_tick;
+                }
+                Err(_) => (),
+            },
+            Event::Disconnected => connected = false,
+            _ => (),
+        }
+    }
+
+    restore_terminal()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Options;
+    use std::time::Instant;
+
+    #[test]
+    fn spectator_not_reading_is_dropped_without_holding_up_the_game() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut broadcaster = Broadcaster::bind("127.0.0.1:0").unwrap();
+        // connected, but never reading
+        let _client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        let _ = stream.set_write_timeout(Some(Duration::from_secs(1)));
+        broadcaster.pending.lock().unwrap().push(spectator(stream));
+
+        let options = Options {
+            width: 200,
+            height: 200,
+            seed: Some(1),
+            ..Options::default()
+        };
+        let mut game = Game::new(&options);
+        // every snapshot is a few hundred kilobytes
+        game.walls = (0..200)
+            .flat_map(|y| (100..200).map(move |x| Point::new(x, y)))
+            .collect();
+
+        for tick in 0..400 {
+            // going back a tick sends another snapshot
+            game.ticks = tick % 2;
+
+            let start = Instant::now();
+            broadcaster.publish(&game);
+            assert!(start.elapsed() < Duration::from_millis(500));
+        }
+
+        assert!(broadcaster.spectators.is_empty());
+    }
+}
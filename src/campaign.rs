@@ -0,0 +1,256 @@
+use crate::event::{Event, EventHandle};
+use crate::game::{Game, Options};
+use crate::interface::{play, restore_terminal, setup_terminal};
+use crate::level::Level;
+use crate::stats::Source;
+use crossterm::event::KeyCode;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use tui::layout::{Alignment, Rect};
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Paragraph};
+
+/// The levels of the campaign in the order they are played.
+const LEVELS: [&str; 6] = [
+    include_str!("../levels/01-garden.txt"),
+    include_str!("../levels/02-pillars.txt"),
+    include_str!("../levels/03-corridors.txt"),
+    include_str!("../levels/04-wormholes.txt"),
+    include_str!("../levels/05-patrol.txt"),
+    include_str!("../levels/06-gauntlet.txt"),
+];
+
+pub fn levels() -> Vec<Level> {
+    LEVELS
+        .iter()
+        .map(|text| Level::parse(text).expect("campaign levels are valid"))
+        .collect()
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct LevelProgress {
+    pub completed: bool,
+    pub best: usize,
+}
+
+/// Progress through the campaign, stored in the user's data directory and
+/// keyed by level name.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Progress {
+    levels: BTreeMap<String, LevelProgress>,
+}
+
+impl Progress {
+    pub fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("snake").join("campaign.json"))
+    }
+
+    /// Reads the progress, starting over if there is none yet or the file
+    /// can't be read.
+    pub fn load() -> Self {
+        Progress::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        let path = Progress::path().ok_or("no data directory")?;
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+
+        Ok(())
+    }
+
+    pub fn level(&self, level: &Level) -> LevelProgress {
+        self.levels.get(&level.name).cloned().unwrap_or_default()
+    }
+
+    /// The first level is always unlocked, every other one once the level
+    /// before it is completed.
+    pub fn is_unlocked(&self, levels: &[Level], idx: usize) -> bool {
+        idx == 0 || self.level(&levels[idx - 1]).completed
+    }
+
+    /// Records a finished game on the level and returns true if it is a new
+    /// best score.
+    pub fn record(&mut self, level: &Level, game: &Game) -> bool {
+        let progress = self.levels.entry(level.name.clone()).or_default();
+        let best = game.score() > progress.best;
+
+        progress.completed |= game.is_completed();
+        progress.best = progress.best.max(game.score());
+
+        best
+    }
+
+    /// Records a finished game on the level in place of the one recorded with
+    /// `previous` as the progress before it, for a run rewound after it ended.
+    pub fn replace(&mut self, level: &Level, previous: LevelProgress, game: &Game) -> bool {
+        self.levels.insert(level.name.clone(), previous);
+        self.record(level, game)
+    }
+}
+
+/// Shows the level select screen and plays the chosen levels until the
+/// player quits.
+pub fn run() -> Result<(), Box<dyn Error>> {
+    let levels = levels();
+    let mut progress = Progress::load();
+    let mut terminal = setup_terminal()?;
+    let events = EventHandle::without_ticks();
+
+    // start on the first level that isn't completed yet
+    let mut selected = (0..levels.len())
+        .find(|&idx| !progress.level(&levels[idx]).completed)
+        .unwrap_or(0);
+    let mut message = None;
+
+    loop {
+        terminal.draw(|f| {
+            let mut lines = vec![
+                Spans::from(""),
+                Spans::from("Select a level"),
+                Spans::from(""),
+            ];
+
+            for (idx, level) in levels.iter().enumerate() {
+                let level_progress = progress.level(level);
+                let (status, color) = if level_progress.completed {
+                    (
+                        format!("completed, best {}", level_progress.best),
+                        Color::Green,
+                    )
+                } else if progress.is_unlocked(&levels, idx) {
+                    (format!("best {}", level_progress.best), Color::Yellow)
+                } else {
+                    ("locked".to_string(), Color::DarkGray)
+                };
+
+                let mut style = Style::default().fg(color);
+                if idx == selected {
+                    style = style.add_modifier(Modifier::REVERSED);
+                }
+
+                lines.push(Spans::from(Span::styled(
+                    format!(" {}. {:<14}{:>20} ", idx + 1, level.name, status),
+                    style,
+                )));
+            }
+
+            lines.push(Spans::from(""));
+            lines.push(Spans::from(
+                message.unwrap_or("Move: ↑↓ jk ws, Play: enter, Quit: q"),
+            ));
+
+            let width = 44.min(f.size().width);
+            let height = (lines.len() as u16 + 2).min(f.size().height);
+            let area = Rect {
+                x: (f.size().width - width) / 2,
+                y: (f.size().height - height) / 2,
+                width,
+                height,
+            };
+
+            let menu = Paragraph::new(lines).alignment(Alignment::Center).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Green))
+                    .title("Campaign"),
+            );
+            f.render_widget(menu, area);
+        })?;
+
+        message = None;
+
+        if let Event::Input(key) = events.next()? {
+            match key.code {
+                KeyCode::Char('q') => break,
+                KeyCode::Char('k') | KeyCode::Char('w') | KeyCode::Up => {
+                    selected = selected.saturating_sub(1)
+                }
+                KeyCode::Char('j') | KeyCode::Char('s') | KeyCode::Down => {
+                    selected = (selected + 1).min(levels.len() - 1)
+                }
+                KeyCode::Enter | KeyCode::Char(' ') => {
+                    if !progress.is_unlocked(&levels, selected) {
+                        message = Some("Complete the previous level first");
+                        continue;
+                    }
+
+                    let level = &levels[selected];
+                    let mut options = Options::default();
+                    options.set_level(level.clone());
+                    options.source = Source::Campaign;
+                    // progress before the run was recorded
+                    let mut previous = progress.level(level);
+
+                    play(
+                        &mut terminal,
+                        &events,
+                        &options,
+                        Game::new(&options),
+                        |game, replace| {
+                            let best = if replace {
+                                progress.replace(level, previous.clone(), game)
+                            } else {
+                                previous = progress.level(level);
+                                progress.record(level, game)
+                            };
+                            // losing progress isn't worth interrupting the game for
+                            let _ = progress.save();
+
+                            let note = if best {
+                                Span::styled(
+                                    format!("New best {}. ", game.score()),
+                                    Style::default().fg(Color::Yellow),
+                                )
+                            } else {
+                                Span::raw(format!("Score {}. ", game.score()))
+                            };
+                            vec![note]
+                        },
+                    )?;
+                    events.set_speed(0.0);
+
+                    if progress.level(level).completed && selected + 1 < levels.len() {
+                        selected += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    restore_terminal()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rewound_run_replaces_its_progress() {
+        let level = &levels()[0];
+        let mut options = Options::default();
+        options.set_level(level.clone());
+        let mut progress = Progress::default();
+        let mut game = Game::new(&options);
+
+        let previous = progress.level(level);
+        game.snakes[0].score = 7;
+        progress.record(level, &game);
+
+        // the run is rewound and ends with fewer points
+        game.snakes[0].score = 3;
+        assert!(progress.replace(level, previous, &game));
+        assert_eq!(progress.level(level).best, 3);
+    }
+}
@@ -0,0 +1,151 @@
+use crate::interface::{draw_board, game_grid};
+use crate::replay::{Player, Replay};
+use serde_json::json;
+use std::cell::Cell;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::rc::Rc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tui::backend::CrosstermBackend;
+use tui::layout::Rect;
+use tui::style::Color;
+use tui::text::Spans;
+use tui::{Terminal, TerminalOptions, Viewport};
+
+/// Status lines below the board in converted replays.
+const STATUS_HEIGHT: u16 = 1;
+
+/// Seconds the last frame of a converted replay stays on screen.
+const FINAL_FRAME: f64 = 2.0;
+
+/// An asciicast v2 recording: a header line followed by a line for every
+/// chunk of output with the seconds since the start of the recording.
+pub struct Cast {
+    file: BufWriter<File>,
+}
+
+impl Cast {
+    pub fn create(path: &str, width: u16, height: u16) -> Result<Self, Box<dyn Error>> {
+        let mut file = BufWriter::new(File::create(path)?);
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+        let header = json!({
+            "version": 2,
+            "width": width,
+            "height": height,
+            "timestamp": timestamp,
+        });
+
+        writeln!(file, "{}", header)?;
+
+        Ok(Cast { file })
+    }
+
+    pub fn output(&mut self, time: f64, data: &[u8]) -> io::Result<()> {
+        let event = json!([time, "o", String::from_utf8_lossy(data)]);
+        writeln!(self.file, "{}", event)
+    }
+}
+
+/// Passes everything written to it on to `inner` and adds it to the cast
+/// whenever it is flushed, which the backend does once per frame.
+pub struct CastWriter<W: Write> {
+    inner: W,
+    cast: Cast,
+    pending: Vec<u8>,
+    clock: Box<dyn Fn() -> f64>,
+}
+
+impl<W: Write> CastWriter<W> {
+    /// Stamps the output with the time passed since the writer was created.
+    pub fn new(inner: W, cast: Cast) -> Self {
+        let start = Instant::now();
+        CastWriter::with_clock(inner, cast, move || start.elapsed().as_secs_f64())
+    }
+
+    /// Stamps the output with the seconds returned by `clock`.
+    pub fn with_clock(inner: W, cast: Cast, clock: impl Fn() -> f64 + 'static) -> Self {
+        CastWriter {
+            inner,
+            cast,
+            pending: Vec::new(),
+            clock: Box::new(clock),
+        }
+    }
+}
+
+impl<W: Write> Write for CastWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.pending.extend_from_slice(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()?;
+
+        if !self.pending.is_empty() {
+            self.cast.output((self.clock)(), &self.pending)?;
+            self.pending.clear();
+        }
+        self.cast.file.flush()
+    }
+}
+
+/// Renders every tick of the replay into a cast, timed at the speed the game
+/// was played at. Nothing is shown on the terminal.
+pub fn convert(replay_path: &str, cast_path: &str) -> Result<(), Box<dyn Error>> {
+    let replay = Replay::load(replay_path)?;
+    let speed = replay.speed;
+    let (width, height) = replay.start.board;
+    let area = Rect::new(
+        0,
+        0,
+        width as u16 * 2 + 2,
+        height as u16 + 2 + STATUS_HEIGHT,
+    );
+
+    let time = Rc::new(Cell::new(0.0));
+    let clock = Rc::clone(&time);
+    let cast = Cast::create(cast_path, area.width, area.height)?;
+    let writer = CastWriter::with_clock(io::sink(), cast, move || clock.get());
+    let mut terminal = Terminal::with_options(
+        CrosstermBackend::new(writer),
+        TerminalOptions {
+            viewport: Viewport::fixed(area),
+        },
+    )?;
+
+    let mut player = Player::new(replay);
+
+    for tick in 0..=player.len() {
+        player.seek(tick);
+        time.set(tick as f64 / speed);
+
+        let game = player.game();
+        let status = Spans::from(format!(
+            "Score {} | Length {}",
+            game.score(),
+            game.snake().len()
+        ));
+        let border_color = if game.is_game_over() {
+            Color::Red
+        } else if game.is_won() || game.is_completed() {
+            Color::Cyan
+        } else {
+            Color::Green
+        };
+
+        terminal.draw(|f| {
+            draw_board(f, game_grid(game), border_color, status);
+        })?;
+    }
+
+    // one more chunk keeps the last frame on screen for a while
+    time.set(time.get() + FINAL_FRAME);
+    terminal.hide_cursor()?;
+
+    Ok(())
+}
@@ -0,0 +1,210 @@
+use crate::event::{Event, EventHandle};
+use crate::game::Direction;
+use crate::interface::{
+    draw_apple, draw_board, draw_snake, new_grid, restore_terminal, setup_terminal, snake_colors,
+};
+use crate::net::{self, ClientMessage, LobbyPlayer, PlayerState, ServerMessage};
+use crossterm::event::KeyCode;
+use std::error::Error;
+use std::io::{BufRead, BufReader};
+use std::net::TcpStream;
+use std::thread;
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+
+/// Joins a multiplayer server and plays on it until the player quits or the
+/// connection is lost.
+pub fn run(addr: &str, name: &str) -> Result<(), Box<dyn Error>> {
+    let mut stream = TcpStream::connect(addr)?;
+    net::send(
+        &mut stream,
+        &ClientMessage::Hello {
+            name: name.to_string(),
+        },
+    )?;
+
+    let mut terminal = setup_terminal()?;
+    let events = EventHandle::without_ticks();
+    let tx = events.sender();
+    let reader = stream.try_clone()?;
+
+    thread::spawn(move || {
+        for line in BufReader::new(reader).lines().map_while(Result::ok) {
+            if tx.send(Event::Message(line)).is_err() {
+                return;
+            }
+        }
+        let _ = tx.send(Event::Disconnected);
+    });
+
+    let mut id = None;
+    let mut board = (0, 0);
+    let mut lobby: Vec<LobbyPlayer> = Vec::new();
+    let mut players: Vec<PlayerState> = Vec::new();
+    let mut playing = false;
+    let mut ready = false;
+    let mut connected = true;
+
+    loop {
+        let me = players.iter().find(|player| Some(player.id) == id);
+
+        let mut grid = new_grid(board);
+        if let Some(player) = players.first() {
+            draw_apple(&mut grid, &player.apple);
+        }
+        // the player's own snake gets the usual colors, the others the rivals'
+        for (idx, player) in players.iter().enumerate() {
+            if player.alive || Some(player.id) == id {
+                let (head_color, body_color) = if Some(player.id) == id {
+                    snake_colors(0)
+                } else {
+                    snake_colors(idx + 1)
+                };
+                draw_snake(&mut grid, player.snake.iter(), head_color, body_color);
+            }
+        }
+
+        let status = if !connected {
+            Spans::from(vec![
+                Span::styled(
+                    "Disconnected from the server",
+                    Style::default().fg(Color::Red),
+                ),
+                Span::raw(", press q to quit"),
+            ])
+        } else if playing {
+            scoreboard(&players, id)
+        } else {
+            lobby_status(&lobby, id, ready)
+        };
+
+        let border_color = match me {
+            _ if !connected => Color::Red,
+            Some(player) if playing && player.alive => Color::Green,
+            Some(_) if playing => Color::Red,
+            _ => Color::Yellow,
+        };
+
+        terminal.draw(|f| {
+            draw_board(f, grid, border_color, status);
+        })?;
+
+        let message = match events.next()? {
+            Event::Input(key) => {
+                let direction = match key.code {
+                    KeyCode::Char('q') => break,
+                    KeyCode::Char('a') | KeyCode::Char('h') | KeyCode::Left => Direction::Left,
+                    KeyCode::Char('s') | KeyCode::Char('j') | KeyCode::Down => Direction::Down,
+                    KeyCode::Char('w') | KeyCode::Char('k') | KeyCode::Up => Direction::Up,
+                    KeyCode::Char('d') | KeyCode::Char('l') | KeyCode::Right => Direction::Right,
+                    KeyCode::Enter | KeyCode::Char(' ') if connected && !playing => {
+                        ready = !ready;
+                        net::send(&mut stream, &ClientMessage::Ready { ready })?;
+                        continue;
+                    }
+                    _ => continue,
+                };
+
+                if connected && playing {
+                    net::send(&mut stream, &ClientMessage::Input { direction })?;
+                }
+
+                continue;
+            }
+            Event::Message(message) => message,
+            Event::Disconnected => {
+                connected = false;
+                continue;
+            }
+            Event::Tick => continue,
+        };
+
+        match serde_json::from_str(&message) {
+            Ok(ServerMessage::Welcome {
+                id: my_id,
+                width,
+                height,
+            }) => {
+                id = Some(my_id);
+                board = (width, height);
+            }
+            Ok(ServerMessage::Lobby { players: list }) => {
+                lobby = list;
+                playing = false;
+                ready = lobby
+                    .iter()
+                    .any(|player| Some(player.id) == id && player.ready);
+            }
+            Ok(ServerMessage::State { players: list, .. }) => {
+                players = list;
+                playing = true;
+            }
+            Err(_) => (),
+        }
+    }
+
+    restore_terminal()
+}
+
+fn name_style(player_id: usize, id: Option<usize>) -> Style {
+    if Some(player_id) == id {
+        Style::default().add_modifier(Modifier::BOLD)
+    } else {
+        Style::default()
+    }
+}
+
+fn scoreboard(players: &[PlayerState], id: Option<usize>) -> Spans<'static> {
+    let mut spans = Vec::new();
+
+    for player in players {
+        if !spans.is_empty() {
+            spans.push(Span::raw(" | "));
+        }
+
+        let color = if player.alive {
+            Color::Green
+        } else {
+            Color::Red
+        };
+        spans.push(Span::styled(
+            player.name.clone(),
+            name_style(player.id, id).fg(color),
+        ));
+        spans.push(Span::raw(format!(": {}", player.score)));
+    }
+
+    Spans::from(spans)
+}
+
+fn lobby_status(lobby: &[LobbyPlayer], id: Option<usize>, ready: bool) -> Spans<'static> {
+    let mut spans = vec![Span::raw("Lobby: ")];
+
+    for (idx, player) in lobby.iter().enumerate() {
+        if idx != 0 {
+            spans.push(Span::raw(", "));
+        }
+
+        let color = if player.ready {
+            Color::Green
+        } else {
+            Color::Yellow
+        };
+        spans.push(Span::styled(
+            player.name.clone(),
+            name_style(player.id, id).fg(color),
+        ));
+
+        if let Some(score) = player.score {
+            spans.push(Span::raw(format!(" ({})", score)));
+        }
+    }
+
+    spans.push(Span::raw(if ready {
+        " - waiting for the others, enter: not ready, q: quit"
+    } else {
+        " - enter: ready, q: quit"
+    }));
+
+    Spans::from(spans)
+}
@@ -0,0 +1,332 @@
+use crate::event::{Event, EventHandle};
+use crate::game::{Direction, Game, Options, Point};
+use crate::interface::{
+    draw_board, draw_hazards, draw_items, draw_level, draw_snake, new_grid, play, restore_terminal,
+    setup_terminal, snake_colors, Grid, PORTAL_CHAR,
+};
+use crate::item::{Item, ItemKind};
+use crate::level::Level;
+use crate::stats::Source;
+use crossterm::event::KeyCode;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+
+/// Where a new level is saved when no file is given.
+const DEFAULT_PATH: &str = "level.txt";
+
+/// Portal pairs a level file can tell apart, one for every lowercase letter.
+const MAX_PORTALS: usize = 26;
+
+struct Editor {
+    level: Level,
+    path: String,
+    cursor: Point,
+    /// First end of a portal waiting for its other end.
+    portal_end: Option<Point>,
+    /// Puts walls on every cell the cursor moves over.
+    painting: bool,
+    saved: bool,
+    message: Option<String>,
+}
+
+impl Editor {
+    fn new(path: Option<&String>, width: i32, height: i32) -> Result<Self, Box<dyn Error>> {
+        let path = path.map_or(DEFAULT_PATH, String::as_str);
+
+        let level = if Path::new(path).exists() {
+            Level::load(path)?
+        } else if width < 1 || height < 1 {
+            return Err("the board must be at least 1x1".into());
+        } else {
+            Level {
+                width,
+                height,
+                ..Level::default()
+            }
+        };
+
+        Ok(Editor {
+            level,
+            path: path.to_string(),
+            cursor: Point::new(0, 0),
+            portal_end: None,
+            painting: false,
+            saved: true,
+            message: None,
+        })
+    }
+
+    fn move_cursor(&mut self, direction: Direction) {
+        let next = self.cursor.adjacent_point(&direction);
+
+        if next.x < 0 || next.x >= self.level.width || next.y < 0 || next.y >= self.level.height {
+            return;
+        }
+
+        self.cursor = next;
+        if self.painting && !self.level.walls.contains(&self.cursor) {
+            self.clear();
+            self.level.walls.push(self.cursor.clone());
+        }
+    }
+
+    /// Removes everything on the cell under the cursor, including hazards
+    /// passing through it.
+    fn clear(&mut self) {
+        let p = self.cursor.clone();
+
+        self.level.walls.retain(|wall| *wall != p);
+        self.level.portals.retain(|(a, b)| *a != p && *b != p);
+        self.level.items.retain(|(position, _)| *position != p);
+        self.level
+            .hazards
+            .retain(|hazard| !hazard.cells().contains(&p));
+
+        if matches!(&self.level.start, Some((head, _)) if *head == p) {
+            self.level.start = None;
+        }
+
+        if self.portal_end.as_ref() == Some(&p) {
+            self.portal_end = None;
+        }
+
+        self.saved = false;
+    }
+
+    fn toggle_wall(&mut self) {
+        let is_wall = self.level.walls.contains(&self.cursor);
+
+        self.clear();
+        if !is_wall {
+            self.level.walls.push(self.cursor.clone());
+        }
+    }
+
+    /// Places the head of the snake under the cursor, or turns it clockwise
+    /// if it is already there.
+    fn place_start(&mut self) {
+        let direction = match &self.level.start {
+            Some((head, direction)) if *head == self.cursor => match direction {
+                Direction::Up => Direction::Right,
+                Direction::Right => Direction::Down,
+                Direction::Down => Direction::Left,
+                Direction::Left => Direction::Up,
+            },
+            _ => {
+                self.clear();
+                Direction::Right
+            }
+        };
+
+        self.level.start = Some((self.cursor.clone(), direction));
+        self.saved = false;
+    }
+
+    /// Removes the portal under the cursor, or places one end of a portal.
+    /// The pair is added once both ends are placed.
+    fn place_portal(&mut self) {
+        let p = self.cursor.clone();
+
+        if self.level.portals.iter().any(|(a, b)| *a == p || *b == p) {
+            self.clear();
+            return;
+        }
+
+        match self.portal_end.take() {
+            Some(end) if end != p => {
+                self.clear();
+                self.level.portals.push((end, p));
+            }
+            Some(_) => (),
+            None if self.level.portals.len() >= MAX_PORTALS => {
+                self.message = Some(format!("A level has at most {} portals", MAX_PORTALS));
+            }
+            None => {
+                self.clear();
+                self.portal_end = Some(p);
+                self.message = Some("Place the other end of the portal".to_string());
+            }
+        }
+    }
+
+    /// Cycles the item under the cursor through every kind and back to none.
+    fn cycle_item(&mut self) {
+        let p = self.cursor.clone();
+        let current = self
+            .level
+            .items
+            .iter()
+            .find(|(position, _)| *position == p)
+            .map(|(_, kind)| *kind);
+        let next = match current {
+            Some(kind) => ItemKind::ALL
+                .iter()
+                .position(|other| *other == kind)
+                .and_then(|idx| ItemKind::ALL.get(idx + 1)),
+            None => ItemKind::ALL.first(),
+        };
+
+        self.clear();
+        if let Some(kind) = next {
+            self.level.items.push((p, *kind));
+        }
+    }
+
+    fn options(&self) -> Options {
+        let mut options = Options::default();
+        options.set_level(self.level.clone());
+        options.source = Source::TestPlay;
+        options
+    }
+
+    /// Writes the level to its file, unless it wouldn't load again.
+    fn save(&mut self) {
+        let text = self.level.to_string();
+
+        self.message = Some(match Level::parse(&text) {
+            Err(err) => format!("Can't save: {}", err),
+            Ok(_) => match fs::write(&self.path, text) {
+                Err(err) => format!("Can't save: {}", err),
+                Ok(()) => {
+                    self.saved = true;
+                    format!("Saved to {}", self.path)
+                }
+            },
+        });
+    }
+
+    fn grid(&self) -> Grid {
+        let level = &self.level;
+        let mut grid = new_grid((level.width, level.height));
+
+        draw_level(&mut grid, &level.walls, &level.portals);
+
+        if let Some(p) = &self.portal_end {
+            grid[p.y as usize][p.x as usize] =
+                Span::styled(PORTAL_CHAR, Style::default().fg(Color::White));
+        }
+
+        let items: Vec<Item> = level
+            .items
+            .iter()
+            .map(|(position, kind)| Item {
+                kind: *kind,
+                position: position.clone(),
+                expires: u64::MAX,
+            })
+            .collect();
+        draw_items(&mut grid, &items);
+
+        let inside = |p: &Point| p.x >= 0 && p.x < level.width && p.y >= 0 && p.y < level.height;
+        let body = Game::initial_body(&self.options());
+        let (head_color, body_color) = snake_colors(0);
+        draw_snake(
+            &mut grid,
+            body.iter().filter(|p| inside(p)),
+            head_color,
+            body_color,
+        );
+
+        // routes are drawn dimmed below the hazards
+        for hazard in &level.hazards {
+            for p in hazard.cells() {
+                grid[p.y as usize][p.x as usize] =
+                    Span::styled("··", Style::default().fg(Color::DarkGray));
+            }
+        }
+        draw_hazards(&mut grid, level.hazards.iter().map(|h| &h.position));
+
+        let cell = &mut grid[self.cursor.y as usize][self.cursor.x as usize];
+        cell.style = cell.style.add_modifier(Modifier::REVERSED);
+
+        grid
+    }
+}
+
+/// Opens the level file in the editor, or a new level of the given size if
+/// the file doesn't exist yet.
+pub fn run(path: Option<&String>, width: i32, height: i32) -> Result<(), Box<dyn Error>> {
+    let mut editor = Editor::new(path, width, height)?;
+    let mut terminal = setup_terminal()?;
+    let events = EventHandle::without_ticks();
+    let mut quitting = false;
+
+    loop {
+        terminal.draw(|f| {
+            let status = match &editor.message {
+                Some(message) => Spans::from(message.as_str()),
+                None => Spans::from(vec![
+                    Span::styled(
+                        format!("({}, {}) ", editor.cursor.x, editor.cursor.y),
+                        Style::default().fg(Color::Cyan),
+                    ),
+                    Span::raw(if editor.painting { "Painting | " } else { "" }),
+                    Span::raw(
+                        "Move: hjkl ←↓↑→, Wall: space, Paint: p, Start: s, Portal: o, \
+                         Item: i, Clear: x, Play: t, Save: w, Quit: q",
+                    ),
+                ]),
+            };
+            let border_color = if editor.saved {
+                Color::Green
+            } else {
+                Color::Yellow
+            };
+
+            draw_board(f, editor.grid(), border_color, status);
+        })?;
+
+        let Event::Input(key) = events.next()? else {
+            continue;
+        };
+
+        if key.code != KeyCode::Char('q') {
+            quitting = false;
+        }
+        editor.message = None;
+
+        match key.code {
+            KeyCode::Char('q') => {
+                if editor.saved || quitting {
+                    break;
+                }
+                quitting = true;
+                editor.message = Some("Unsaved changes, press q again to quit".to_string());
+            }
+            KeyCode::Char('h') | KeyCode::Left => editor.move_cursor(Direction::Left),
+            KeyCode::Char('j') | KeyCode::Down => editor.move_cursor(Direction::Down),
+            KeyCode::Char('k') | KeyCode::Up => editor.move_cursor(Direction::Up),
+            KeyCode::Char('l') | KeyCode::Right => editor.move_cursor(Direction::Right),
+            KeyCode::Char(' ') => editor.toggle_wall(),
+            KeyCode::Char('p') => editor.painting = !editor.painting,
+            KeyCode::Char('s') => editor.place_start(),
+            KeyCode::Char('o') => editor.place_portal(),
+            KeyCode::Char('i') => editor.cycle_item(),
+            KeyCode::Char('x') | KeyCode::Delete | KeyCode::Backspace => editor.clear(),
+            KeyCode::Char('w') => editor.save(),
+            KeyCode::Char('t') => {
+                let options = editor.options();
+
+                match Game::check_options(&options) {
+                    Err(err) => editor.message = Some(format!("Can't play: {}", err)),
+                    Ok(()) => {
+                        play(
+                            &mut terminal,
+                            &events,
+                            &options,
+                            Game::new(&options),
+                            |_, _| Vec::new(),
+                        )?;
+                        events.set_speed(0.0);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    restore_terminal()
+}
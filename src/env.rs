@@ -0,0 +1,268 @@
+use crate::game::{Cause, Direction, Game, Options, Point};
+
+/// Encoding of the observations returned by [`SnakeEnv`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ObservationKind {
+    Grid,
+    Features,
+    Coordinates,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Observation {
+    /// Channels first tensor of shape `[3, height, width]` flattened in row
+    /// major order. The channels hold the head, the body and the apple.
+    Grid(Vec<f32>),
+    /// Danger in each direction, the current direction one-hot encoded, the
+    /// apple's position relative to the head and the normalized length.
+    Features(Vec<f32>),
+    /// Raw coordinates of the snake from tail to head and of the apple.
+    Coordinates { snake: Vec<Point>, apple: Point },
+}
+
+/// Rewards given for the outcome of a step.
+#[derive(Clone, Debug)]
+pub struct Rewards {
+    pub apple: f32,
+    /// Given when the snake crashes, not when the time runs out or the board
+    /// fills up.
+    pub death: f32,
+    pub step: f32,
+    /// Multiplied by how much closer the head got to the apple in manhattan
+    /// distance. Negative when the snake moves away from the apple.
+    pub distance: f32,
+}
+
+impl Default for Rewards {
+    fn default() -> Self {
+        Rewards {
+            apple: 1.0,
+            death: -1.0,
+            step: -0.01,
+            distance: 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Info {
+    pub score: usize,
+    pub length: usize,
+    pub ticks: u64,
+    pub ate_apple: bool,
+    pub won: bool,
+    /// Why the game ended once it is over.
+    pub cause: Option<Cause>,
+}
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+];
+
+/// Headless environment for training agents, modeled after the gym API.
+///
+/// The game only advances when [`SnakeEnv::step`] is called and apples are
+/// placed from the seed given to [`SnakeEnv::reset`], so an episode is fully
+/// determined by its seed and actions.
+pub struct SnakeEnv {
+    options: Options,
+    observation_kind: ObservationKind,
+    rewards: Rewards,
+    game: Game,
+}
+
+impl SnakeEnv {
+    pub fn new(options: Options, observation_kind: ObservationKind, rewards: Rewards) -> Self {
+        let game = Game::new(&options);
+
+        SnakeEnv {
+            options,
+            observation_kind,
+            rewards,
+            game,
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.options.seed = Some(seed);
+        self.game = Game::new(&self.options);
+
+        self.observe()
+    }
+
+    pub fn step(&mut self, action: Direction) -> (Observation, f32, bool, Info) {
+        if !self.game.is_running() {
+            return (self.observe(), 0.0, true, self.info(false));
+        }
+
+        // the score counts ticks in survival and golden apples, and items
+        // change the length, so apples are counted on their own
+        let apples = self.game.snakes[0].apples;
+        let distance = self.head().manhattan_distance(&self.game.apple) as f32;
+
+        self.game.move_snake(action);
+
+        let ate_apple = self.game.snakes[0].apples > apples;
+        let info = self.info(ate_apple);
+        let mut reward = self.rewards.step;
+
+        if ate_apple {
+            reward += self.rewards.apple;
+        } else if self
+            .game
+            .cause()
+            .is_some_and(|cause| cause.cell().is_some())
+        {
+            reward += self.rewards.death;
+        } else {
+            let new_distance = self.head().manhattan_distance(&self.game.apple) as f32;
+            reward += self.rewards.distance * (distance - new_distance);
+        }
+
+        (self.observe(), reward, !self.game.is_running(), info)
+    }
+
+    fn head(&self) -> &Point {
+        self.game.snake().back().unwrap()
+    }
+
+    fn info(&self, ate_apple: bool) -> Info {
+        let length = self.game.snake().len();
+
+        Info {
+            score: self.game.score(),
+            length,
+            ticks: self.game.ticks,
+            ate_apple,
+            won: self.game.is_won(),
+            cause: self.game.cause().cloned(),
+        }
+    }
+
+    pub fn observe(&self) -> Observation {
+        match self.observation_kind {
+            ObservationKind::Grid => self.observe_grid(),
+            ObservationKind::Features => self.observe_features(),
+            ObservationKind::Coordinates => Observation::Coordinates {
+                snake: self.game.snake().iter().cloned().collect(),
+                apple: self.game.apple.clone(),
+            },
+        }
+    }
+
+    fn observe_grid(&self) -> Observation {
+        let (width, height) = (self.game.board.0 as usize, self.game.board.1 as usize);
+        let plane = width * height;
+        let mut grid = vec![0.0; 3 * plane];
+        let idx = |p: &Point| p.y as usize * width + p.x as usize;
+
+        grid[idx(self.head())] = 1.0;
+        for p in self.game.snake().iter().rev().skip(1) {
+            grid[plane + idx(p)] = 1.0;
+        }
+        grid[2 * plane + idx(&self.game.apple)] = 1.0;
+
+        Observation::Grid(grid)
+    }
+
+    fn observe_features(&self) -> Observation {
+        let head = self.head();
+        let apple = &self.game.apple;
+        let mut features = Vec::with_capacity(13);
+
+        for direction in &DIRECTIONS {
+            let danger = self.is_deadly(head, direction);
+            features.push(danger as u8 as f32);
+        }
+
+        for direction in &DIRECTIONS {
+            features.push((self.game.dir() == direction) as u8 as f32);
+        }
+
+        features.push((apple.y < head.y) as u8 as f32);
+        features.push((apple.y > head.y) as u8 as f32);
+        features.push((apple.x < head.x) as u8 as f32);
+        features.push((apple.x > head.x) as u8 as f32);
+        features.push(self.game.snake().len() as f32 / self.game.board_size() as f32);
+
+        Observation::Features(features)
+    }
+
+    fn is_deadly(&self, head: &Point, direction: &Direction) -> bool {
+        match self.game.next_position(head, direction) {
+            Some(point) => self.game.check_overlap(&point) || self.game.hazard_threatens(&point, 1),
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mode::Mode;
+    use crate::solver::Solver;
+
+    /// Plays an episode with the moves of the solver.
+    fn episode(env: &mut SnakeEnv, seed: u64) -> Vec<(Observation, f32, bool)> {
+        let mut steps = vec![(env.reset(seed), 0.0, false)];
+        let mut solver = Solver::new(env.game());
+
+        for _ in 0..300 {
+            let action = solver.next_direction(env.game());
+            let (observation, reward, done, _) = env.step(action);
+            steps.push((observation, reward, done));
+        }
+
+        steps
+    }
+
+    #[test]
+    fn same_seed_gives_same_episode() {
+        let options = Options {
+            width: 10,
+            height: 10,
+            ..Options::default()
+        };
+
+        for kind in [
+            ObservationKind::Grid,
+            ObservationKind::Features,
+            ObservationKind::Coordinates,
+        ] {
+            let mut env = SnakeEnv::new(options.clone(), kind, Rewards::default());
+            let first = episode(&mut env, 3);
+            let mut env = SnakeEnv::new(options.clone(), kind, Rewards::default());
+
+            assert!(first.iter().any(|&(_, reward, _)| reward > 0.0));
+            assert_eq!(first, episode(&mut env, 3));
+        }
+    }
+
+    #[test]
+    fn running_out_of_time_isnt_a_death() {
+        let options = Options {
+            mode: Mode::TimeAttack,
+            time_limit: 1,
+            speed: 3.0,
+            ..Options::default()
+        };
+        let rewards = Rewards::default();
+        let mut env = SnakeEnv::new(options, ObservationKind::Features, rewards.clone());
+        env.reset(0);
+
+        let (_, mut reward, mut done, _) = env.step(Direction::Right);
+        while !done {
+            (_, reward, done, _) = env.step(Direction::Right);
+        }
+
+        assert_eq!(env.game().cause(), Some(&Cause::TimeUp));
+        assert_eq!(reward, rewards.step);
+    }
+}
@@ -1,34 +1,64 @@
 use crossterm::event::{Event as CEvent, KeyEvent};
-use std::sync::mpsc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
 use std::thread;
 use std::time::Duration;
 
+/// How often the tick thread checks for a new speed while stopped.
+const IDLE_INTERVAL: Duration = Duration::from_millis(20);
+
 pub enum Event {
     Input(KeyEvent),
     Tick,
+    Message(String),
+    Disconnected,
 }
 
 pub struct EventHandle {
+    tx: mpsc::Sender<Event>,
     rx: mpsc::Receiver<Event>,
     _input_handle: thread::JoinHandle<()>,
     _tick_handle: thread::JoinHandle<()>,
+    /// Ticks per second, stored as the bits of an `f64`. No ticks are sent
+    /// while it is 0.
+    speed: Arc<AtomicU64>,
 }
 
 impl EventHandle {
     pub fn new(speed: f64) -> EventHandle {
+        EventHandle::spawn(speed)
+    }
+
+    /// Creates a handle that only delivers key events and whatever is sent
+    /// through [`EventHandle::sender`], until a speed is set.
+    pub fn without_ticks() -> EventHandle {
+        EventHandle::spawn(0.0)
+    }
+
+    fn spawn(speed: f64) -> EventHandle {
         let (tx, rx) = mpsc::channel();
-        let tx1 = mpsc::Sender::clone(&tx);
+        let input_tx = mpsc::Sender::clone(&tx);
         let _input_handle = thread::spawn(move || {
             while let Ok(event) = crossterm::event::read() {
                 if let CEvent::Key(key) = event {
-                    if let Err(err) = tx.send(Event::Input(key)) {
+                    if let Err(err) = input_tx.send(Event::Input(key)) {
                         eprintln!("{}", err);
                         return;
                     }
                 }
             }
         });
+        let speed = Arc::new(AtomicU64::new(speed.to_bits()));
+        let tick_speed = Arc::clone(&speed);
+        let tx1 = mpsc::Sender::clone(&tx);
         let _tick_handle = thread::spawn(move || loop {
+            let speed = f64::from_bits(tick_speed.load(Ordering::Relaxed));
+
+            if speed <= 0.0 {
+                thread::sleep(IDLE_INTERVAL);
+                continue;
+            }
+
             if tx1.send(Event::Tick).is_err() {
                 break;
             }
@@ -37,12 +67,23 @@ impl EventHandle {
         });
 
         EventHandle {
+            tx,
             rx,
             _input_handle,
             _tick_handle,
+            speed,
         }
     }
 
+    /// Changes how many ticks are delivered per second.
+    pub fn set_speed(&self, speed: f64) {
+        self.speed.store(speed.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn sender(&self) -> mpsc::Sender<Event> {
+        self.tx.clone()
+    }
+
     pub fn next(&self) -> Result<Event, mpsc::RecvError> {
         self.rx.recv()
     }
@@ -1,19 +1,91 @@
+use crate::hazard::{self, Hazard};
+use crate::item::{self, Effect, Item, ItemKind};
+use crate::level::Level;
+use crate::mode::{self, Mode};
+use crate::rival::Difficulty;
 use crate::solver::PathAlgorithm;
+use crate::stats::Source;
 use clap::ArgMatches;
-use rand::{thread_rng, Rng};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
+use std::fmt;
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Options {
-    width: i32,
-    height: i32,
-    head_x: i32,
-    head_y: i32,
+    pub width: i32,
+    pub height: i32,
+    pub head_x: i32,
+    pub head_y: i32,
     pub speed: f64,
-    length: i32,
-    direction: Direction,
-    borders: bool,
-    self_play: bool,
-    path_alg: PathAlgorithm,
+    pub length: i32,
+    pub direction: Direction,
+    pub borders: bool,
+    pub self_play: bool,
+    pub path_alg: PathAlgorithm,
+    pub seed: Option<u64>,
+    pub bot: Option<String>,
+    pub bot_timeout: u64,
+    pub broadcast: Option<String>,
+    pub rivals: usize,
+    pub rival_level: Difficulty,
+    pub items: bool,
+    pub level: Option<Level>,
+    pub portals: usize,
+    pub hazards: usize,
+    pub mode: Mode,
+    /// Length of a time attack game in seconds.
+    pub time_limit: u64,
+    /// Apples to eat to complete the level.
+    pub goal: Option<usize>,
+    /// Seconds of play that can be rewound, 0 turning rewinding off.
+    #[serde(default)]
+    pub rewind: u64,
+    /// File the replay of the last game played is written to.
+    #[serde(default)]
+    pub record: Option<String>,
+    /// File the frames drawn on the terminal are recorded to as an asciicast.
+    #[serde(default)]
+    pub record_cast: Option<String>,
+    /// Where the game is played from, which decides how it is recorded in the
+    /// history.
+    #[serde(skip)]
+    pub source: Source,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            width: 30,
+            height: 20,
+            head_x: 3,
+            head_y: 3,
+            speed: 10.0,
+            length: 3,
+            direction: Direction::Right,
+            borders: true,
+            self_play: false,
+            path_alg: PathAlgorithm::Bfs,
+            seed: None,
+            bot: None,
+            bot_timeout: 100,
+            broadcast: None,
+            rivals: 0,
+            rival_level: Difficulty::Normal,
+            items: false,
+            level: None,
+            portals: 0,
+            hazards: 0,
+            mode: Mode::Endless,
+            time_limit: 60,
+            goal: None,
+            rewind: 0,
+            record: None,
+            record_cast: None,
+            source: Source::Play,
+        }
+    }
 }
 
 impl From<ArgMatches> for Options {
@@ -32,11 +104,55 @@ impl From<ArgMatches> for Options {
             borders: !matches.get_flag("no_border"),
             self_play: matches.get_flag("self_play"),
             path_alg,
+            seed: matches.get_one::<u64>("seed").copied(),
+            bot: matches.get_one::<String>("bot").cloned(),
+            bot_timeout: *matches.get_one::<u64>("bot_timeout").unwrap(),
+            broadcast: matches.get_one::<String>("broadcast").cloned(),
+            rivals: *matches.get_one::<usize>("rivals").unwrap(),
+            rival_level: Difficulty::from(matches.get_one::<String>("rival_level").unwrap()),
+            items: matches.get_flag("items"),
+            level: None,
+            portals: *matches.get_one::<usize>("portals").unwrap(),
+            hazards: *matches.get_one::<usize>("hazards").unwrap(),
+            mode: Mode::from(matches.get_one::<String>("mode").unwrap()),
+            time_limit: *matches.get_one::<u64>("time_limit").unwrap(),
+            goal: None,
+            rewind: *matches.get_one::<u64>("rewind").unwrap(),
+            record: matches.get_one::<String>("record").cloned(),
+            record_cast: matches.get_one::<String>("record_cast").cloned(),
+            source: Source::Play,
+        }
+    }
+}
+
+impl Options {
+    /// Takes the board size and the settings given in the level.
+    pub fn set_level(&mut self, level: Level) {
+        self.width = level.width;
+        self.height = level.height;
+
+        if let Some((head, direction)) = &level.start {
+            self.head_x = head.x;
+            self.head_y = head.y;
+            self.direction = direction.clone();
+        }
+
+        if let Some(length) = level.length {
+            self.length = length;
         }
+
+        if let Some(speed) = level.speed {
+            self.speed = speed;
+        }
+
+        self.goal = level.goal;
+
+        self.level = Some(level);
     }
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Direction {
     Up,
     Down,
@@ -45,7 +161,7 @@ pub enum Direction {
 }
 
 impl Direction {
-    fn opposite_dir(dir: &Direction) -> Direction {
+    pub fn opposite_dir(dir: &Direction) -> Direction {
         match dir {
             Direction::Left => Direction::Right,
             Direction::Right => Direction::Left,
@@ -67,60 +183,419 @@ impl From<&String> for Direction {
     }
 }
 
-pub struct Game {
-    pub snake: VecDeque<Point>,
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Snake {
+    pub id: usize,
+    /// Body of the snake from its tail to its head.
+    pub body: VecDeque<Point>,
     pub dir: Direction,
+    pub alive: bool,
+    pub score: usize,
+    /// Number of apples eaten, which unlike the score ignores golden apples.
+    #[serde(default)]
+    pub apples: usize,
+}
+
+impl Snake {
+    pub fn head(&self) -> &Point {
+        self.body.back().unwrap()
+    }
+
+    pub fn tail(&self) -> &Point {
+        self.body.front().unwrap()
+    }
+}
+
+/// Where a snake tries to go in a tick.
+struct Move {
+    head: Point,
+    dir: Direction,
+    eats: bool,
+    /// Why the snake dies if it does.
+    death: Option<Cause>,
+}
+
+/// The board and every snake on it. The snake with id 0 is the player's.
+///
+/// All snakes move at the same time. A snake dies when it leaves the board
+/// with borders enabled or runs into a wall or a body, including its own. A
+/// snake entering a portal comes out next to its partner. Hazards move on the
+/// same tick without entering snakes, and a snake whose head runs into one
+/// dies. When two heads meet the longer snake survives and equal snakes both
+/// die, which also decides who gets an apple both snakes reach. The game is
+/// over when the player's snake dies, [`Game::cause`] telling why, and won
+/// when it fills the whole board.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Game {
+    pub snakes: Vec<Snake>,
     pub board: (i32, i32),
     borders: bool,
     pub self_play: bool,
     pub path_alg: PathAlgorithm,
     pub apple: Point,
+    pub walls: Vec<Point>,
+    pub portals: Vec<(Point, Point)>,
+    pub hazards: Vec<Hazard>,
+    /// Positions of the hazards for the next ticks, starting with the current
+    /// one.
+    hazard_forecast: Vec<Vec<Point>>,
+    pub items: Vec<Item>,
+    pub effects: Vec<Effect>,
+    spawn_items: bool,
+    pub mode: Mode,
+    /// Tick a time attack game ends on.
+    time_limit: Option<u64>,
+    /// Apples the player has to eat to complete the level.
+    pub goal: Option<usize>,
+    pub ticks: u64,
+    /// Times the game was stepped, more than `ticks` once slow motion skipped
+    /// some.
+    #[serde(default)]
+    pub steps: u64,
+    /// Set once the game is rewound.
+    #[serde(default)]
+    pub assisted: bool,
+    /// Seed the apples and everything else random were placed with.
+    #[serde(default)]
+    pub seed: u64,
+    rng: ChaCha8Rng,
+    free: FreeCells,
     state: State,
 }
 
 impl Game {
     pub fn new(options: &Options) -> Self {
-        assert!(
-            Game::validate_initial_state(options),
-            "initial state of the snake is invalid"
-        );
-
-        assert!(
-            !options.self_play || options.width % 2 == 0 && options.height % 2 == 0,
-            "height and width must be even for self playing mode"
-        );
-
-        let mut snake = VecDeque::new();
-
-        for i in (0..options.length).rev() {
-            match options.direction {
-                Direction::Up => snake.push_back(Point::new(options.head_x, options.head_y + i)),
-                Direction::Down => snake.push_back(Point::new(options.head_x, options.head_y - i)),
-                Direction::Left => snake.push_back(Point::new(options.head_x + i, options.head_y)),
-                Direction::Right => snake.push_back(Point::new(options.head_x - i, options.head_y)),
-            }
+        if let Err(err) = Game::check_options(options) {
+            panic!("{}", err);
         }
 
+        let seed = options.seed.unwrap_or_else(|| rand::thread_rng().gen());
+
         let mut game = Game {
-            snake,
-            dir: options.direction.clone(),
+            snakes: Vec::new(),
             board: (options.width, options.height),
-            borders: options.borders,
+            borders: options.borders && options.mode != Mode::Zen,
             self_play: options.self_play,
             path_alg: options.path_alg.clone(),
             apple: Point::new(0, 0),
+            walls: Vec::new(),
+            portals: Vec::new(),
+            hazards: Vec::new(),
+            hazard_forecast: Vec::new(),
+            items: Vec::new(),
+            effects: Vec::new(),
+            spawn_items: options.items,
+            mode: options.mode,
+            time_limit: (options.mode == Mode::TimeAttack)
+                .then(|| (options.time_limit as f64 * options.speed).round() as u64),
+            goal: options.goal,
+            ticks: 0,
+            steps: 0,
+            assisted: false,
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            free: FreeCells::new(options.width, options.height),
             state: State::Running,
         };
 
+        if let Some(level) = &options.level {
+            for wall in &level.walls {
+                game.free.remove(wall);
+            }
+
+            for (a, b) in &level.portals {
+                game.free.remove(a);
+                game.free.remove(b);
+            }
+
+            game.walls = level.walls.clone();
+            game.portals = level.portals.clone();
+            game.hazards = level.hazards.clone();
+            game.items = level
+                .items
+                .iter()
+                .map(|(position, kind)| Item {
+                    kind: *kind,
+                    position: position.clone(),
+                    expires: u64::MAX,
+                })
+                .collect();
+        }
+
+        game.add_snake(Game::initial_body(options), options.direction.clone());
+        for _ in 0..options.portals {
+            game.place_portal();
+        }
+        for _ in 0..options.rivals {
+            game.spawn_snake(options.length as usize);
+        }
+        for _ in 0..options.hazards {
+            game.place_hazard();
+        }
+        game.forecast_hazards();
         game.gen_apple();
 
         game
     }
 
+    /// Places a new snake on the board and returns its id. The body is given
+    /// from the tail to the head and must only cover free cells. If the apple
+    /// is under the new snake it is moved elsewhere.
+    pub fn add_snake(&mut self, body: VecDeque<Point>, dir: Direction) -> Option<usize> {
+        if body.is_empty() || body.iter().any(|p| self.free.index(p).flatten().is_none()) {
+            return None;
+        }
+
+        for p in &body {
+            self.free.remove(p);
+        }
+
+        let id = self.snakes.len();
+        let covers_apple = body.contains(&self.apple);
+
+        self.snakes.push(Snake {
+            id,
+            body,
+            dir,
+            alive: true,
+            score: 0,
+            apples: 0,
+        });
+
+        if covers_apple {
+            self.gen_apple();
+        }
+
+        Some(id)
+    }
+
+    /// Body of the player's snake at the start, from the tail to the head.
+    pub fn initial_body(options: &Options) -> VecDeque<Point> {
+        let mut body = VecDeque::new();
+
+        for i in (0..options.length).rev() {
+            match options.direction {
+                Direction::Up => body.push_back(Point::new(options.head_x, options.head_y + i)),
+                Direction::Down => body.push_back(Point::new(options.head_x, options.head_y - i)),
+                Direction::Left => body.push_back(Point::new(options.head_x + i, options.head_y)),
+                Direction::Right => body.push_back(Point::new(options.head_x - i, options.head_y)),
+            }
+        }
+
+        body
+    }
+
+    /// Places a pair of portals on random free cells away from the heads.
+    fn place_portal(&mut self) {
+        let mut ends = Vec::with_capacity(2);
+
+        for _ in 0..100 {
+            if self.free.is_empty() || ends.len() == 2 {
+                break;
+            }
+
+            let point = self.free.sample(&mut self.rng).clone();
+            let near_head = self
+                .snakes
+                .iter()
+                .any(|snake| snake.head().manhattan_distance(&point) <= 2);
+            // a snake coming out of a portal would run into a wall or a portal
+            // next to it
+            let near_tile = self
+                .walls
+                .iter()
+                .chain(self.portals.iter().flat_map(|(a, b)| [a, b]))
+                .chain(&ends)
+                .any(|tile| tile.manhattan_distance(&point) == 1);
+
+            if !near_head && !near_tile {
+                self.free.remove(&point);
+                ends.push(point);
+            }
+        }
+
+        match <[Point; 2]>::try_from(ends) {
+            Ok([a, b]) => self.portals.push((a, b)),
+            Err(ends) => ends.into_iter().for_each(|end| self.free.insert(end)),
+        }
+    }
+
+    /// Places a hazard on a random free cell away from the heads. It circles
+    /// around a small square if there is room for it, otherwise it bounces.
+    fn place_hazard(&mut self) {
+        for _ in 0..100 {
+            if self.free.is_empty() {
+                return;
+            }
+
+            let point = self.free.sample(&mut self.rng).clone();
+            let near_head = self
+                .snakes
+                .iter()
+                .any(|snake| snake.head().manhattan_distance(&point) <= 4);
+
+            if near_head {
+                continue;
+            }
+
+            let circle = Hazard::circle(point.clone(), 3);
+            let hazard = if self.rng.gen_bool(0.5)
+                && circle
+                    .cells()
+                    .iter()
+                    .all(|p| matches!(self.free.index(p), Some(Some(_))))
+            {
+                circle
+            } else {
+                let direction = match self.rng.gen_range(0..4) {
+                    0 => Direction::Up,
+                    1 => Direction::Down,
+                    2 => Direction::Left,
+                    _ => Direction::Right,
+                };
+                Hazard::bounce(point, direction)
+            };
+
+            self.hazards.push(hazard);
+            return;
+        }
+    }
+
+    /// Places a straight snake of the given length at a random free spot,
+    /// facing away from its body. Returns `None` if no spot was found.
+    pub fn spawn_snake(&mut self, length: usize) -> Option<usize> {
+        const DIRECTIONS: [Direction; 4] = [
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+        ];
+
+        for _ in 0..100 {
+            if self.free.is_empty() {
+                return None;
+            }
+
+            let mut point = self.free.sample(&mut self.rng).clone();
+            let dir = DIRECTIONS[self.rng.gen_range(0..DIRECTIONS.len())].clone();
+            let back = Direction::opposite_dir(&dir);
+
+            // leave room in front of the new head and of the existing heads
+            let blocked = |p: &Point| {
+                self.free.index(p).flatten().is_none()
+                    || self
+                        .snakes
+                        .iter()
+                        .any(|snake| snake.alive && snake.head().manhattan_distance(p) <= 2)
+            };
+
+            if blocked(&point.adjacent_point(&dir)) {
+                continue;
+            }
+
+            let mut body = VecDeque::with_capacity(length);
+            for _ in 0..length {
+                if blocked(&point) {
+                    break;
+                }
+
+                body.push_front(point.clone());
+                point = point.adjacent_point(&back);
+            }
+
+            if body.len() == length {
+                return self.add_snake(body, dir);
+            }
+        }
+
+        None
+    }
+
+    /// The player's snake.
+    pub fn snake(&self) -> &VecDeque<Point> {
+        &self.snakes[0].body
+    }
+
+    /// Direction of the player's snake.
+    pub fn dir(&self) -> &Direction {
+        &self.snakes[0].dir
+    }
+
+    /// Returns the score of the player, which is the number of ticks survived
+    /// in survival mode and the apples eaten otherwise.
+    pub fn score(&self) -> usize {
+        match self.mode {
+            Mode::Survival => self.ticks as usize,
+            _ => self.snakes[0].score,
+        }
+    }
+
+    /// Returns the ticks left in a time attack game.
+    pub fn remaining_ticks(&self) -> Option<u64> {
+        self.time_limit
+            .map(|limit| limit.saturating_sub(self.ticks))
+    }
+
+    /// Returns the remaining ticks of an effect on the snake, counting slow
+    /// motion for every snake since it slows the whole game down.
+    pub fn effect_remaining(&self, snake: usize, kind: ItemKind) -> Option<u64> {
+        self.effects
+            .iter()
+            .filter(|effect| {
+                effect.kind == kind && (effect.snake == snake || kind == ItemKind::SlowMotion)
+            })
+            .map(|effect| (effect.until + 1).saturating_sub(self.ticks))
+            .max()
+    }
+
+    fn is_ghost(&self, snake: usize) -> bool {
+        self.effect_remaining(snake, ItemKind::Ghost).is_some()
+    }
+
     pub fn board_size(&self) -> i32 {
         self.board.0 * self.board.1
     }
 
+    pub fn check_options(options: &Options) -> Result<(), &'static str> {
+        if !Game::validate_initial_state(options) {
+            return Err("initial state of the snake is invalid");
+        }
+
+        if options.self_play && (options.width % 2 != 0 || options.height % 2 != 0) {
+            return Err("height and width must be even for self playing mode");
+        }
+
+        // the solver's cycle starts from the tail towards the head
+        if options.self_play && options.length < 2 {
+            return Err("length must be at least 2 for self playing mode");
+        }
+
+        if !(options.speed > 0.0 && options.speed.is_finite()) {
+            return Err("speed must be a positive number");
+        }
+
+        if let Some(level) = &options.level {
+            if level.has_blocked_portal() {
+                return Err("a portal opens onto a wall or another portal");
+            }
+
+            let blocked = Game::initial_body(options).into_iter().any(|p| {
+                level.walls.contains(&p) || level.portals.iter().any(|(a, b)| *a == p || *b == p)
+            });
+
+            if blocked {
+                return Err("the snake starts on a wall or a portal");
+            }
+
+            let body = Game::initial_body(options);
+            if level.hazards.iter().any(|h| body.contains(&h.position)) {
+                return Err("a hazard starts on the snake");
+            }
+        }
+
+        Ok(())
+    }
+
     fn validate_initial_state(options: &Options) -> bool {
         if options.head_x < 0
             || options.head_y < 0
@@ -139,38 +614,523 @@ impl Game {
         }
     }
 
-    pub fn move_snake(&mut self, mut dir: Direction) {
-        let mut new_head = Point::new(self.snake.back().unwrap().x, self.snake.back().unwrap().y);
-        if self.dir == Direction::opposite_dir(&dir) {
-            dir = self.dir.clone();
+    /// Moves the player's snake while the other snakes keep their direction.
+    pub fn move_snake(&mut self, dir: Direction) {
+        self.step(&[dir]);
+    }
+
+    /// Moves every snake at once. `dirs[id]` is the direction chosen for the
+    /// snake with that id, snakes without one keep going in their direction.
+    pub fn step(&mut self, dirs: &[Direction]) {
+        self.steps += 1;
+
+        // in slow motion the game, its clock included, only goes on every
+        // other tick
+        if self.effect_remaining(0, ItemKind::SlowMotion).is_some() && self.steps.is_multiple_of(2)
+        {
+            return;
         }
-        match dir {
-            Direction::Left => new_head.x -= 1,
-            Direction::Right => new_head.x += 1,
-            Direction::Up => new_head.y -= 1,
-            Direction::Down => new_head.y += 1,
+
+        self.ticks += 1;
+        self.update_items();
+        self.move_snakes(dirs);
+
+        if self.mode == Mode::Survival && self.ticks.is_multiple_of(mode::SURVIVAL_WALL_INTERVAL) {
+            self.place_wall();
+        }
+        // hazards don't enter snakes or walls, so they are only forecast once
+        // everything moved
+        self.forecast_hazards();
+
+        if self.is_running() && self.time_limit.is_some_and(|limit| self.ticks >= limit) {
+            self.game_over(Cause::TimeUp);
+        }
+    }
+
+    fn move_snakes(&mut self, dirs: &[Direction]) {
+        let previous_hazards = self.hazard_forecast.first().cloned().unwrap_or_default();
+        self.move_hazards();
+
+        let mut moves: Vec<Option<Move>> = self
+            .snakes
+            .iter()
+            .map(|snake| {
+                if !snake.alive {
+                    return None;
+                }
+
+                let mut dir = dirs.get(snake.id).unwrap_or(&snake.dir).clone();
+                if snake.dir == Direction::opposite_dir(&dir) {
+                    dir = snake.dir.clone();
+                }
+
+                let (head, mut death) = match self.next_position(snake.head(), &dir) {
+                    Some(head) => (head, None),
+                    None => (
+                        snake.head().adjacent_point(&dir),
+                        Some(Cause::Border(snake.head().clone())),
+                    ),
+                };
+
+                // a snake and a hazard swapping cells run into each other too
+                let hazard =
+                    self.hazards
+                        .iter()
+                        .zip(&previous_hazards)
+                        .find(|(hazard, previous)| {
+                            hazard.position == head
+                                || **previous == head && hazard.position == *snake.head()
+                        });
+                if let Some((hazard, _)) = hazard {
+                    death = death.or(Some(Cause::Hazard(hazard.position.clone())));
+                }
+                let eats = head == self.apple;
+
+                Some(Move {
+                    head,
+                    dir,
+                    eats,
+                    death,
+                })
+            })
+            .collect();
+
+        self.resolve_collisions(&mut moves);
+
+        let cuts = if self.mode == Mode::Zen {
+            self.spare_snakes(&mut moves)
+        } else {
+            Vec::new()
+        };
+
+        for (id, mv) in moves.iter().enumerate() {
+            if let Some(Move { death: Some(_), .. }) = mv {
+                self.kill(id);
+            }
+        }
+        let player_death = moves[0].as_ref().and_then(|mv| mv.death.clone());
+
+        let mut ate = false;
+
+        for (snake, mv) in self.snakes.iter_mut().zip(&moves) {
+            if matches!(mv, Some(mv) if mv.death.is_none() && !mv.eats) {
+                // heads are placed after every tail left, so a head may move
+                // into the cell a tail just left
+                let tail = snake.body.pop_front().unwrap();
+
+                // a ghost's body may still cover the cell
+                if !snake.body.contains(&tail) {
+                    self.free.insert(tail);
+                }
+            }
+        }
+
+        for (snake, mv) in self.snakes.iter_mut().zip(moves) {
+            if let Some(mv) = mv.filter(|mv| mv.death.is_none()) {
+                ate |= mv.eats;
+                snake.score += mv.eats as usize;
+                snake.apples += mv.eats as usize;
+                self.free.remove(&mv.head);
+                snake.body.push_back(mv.head);
+                snake.dir = mv.dir;
+            }
+        }
+
+        for (id, cell) in cuts {
+            self.cut_snake(id, &cell);
+        }
+
+        self.pick_up_items();
+
+        if let Some(cause) = player_death {
+            return self.game_over(cause);
+        }
+
+        if self.goal.is_some_and(|goal| self.snakes[0].apples >= goal) {
+            self.state = State::Completed;
+        } else if ate {
+            self.check_apple();
+        }
+    }
+
+    /// Keeps every snake alive in zen mode. A snake running into a body cuts
+    /// that body at the segment it hits, the cuts are returned to be made
+    /// once the snakes moved. Any other collision cuts the snake in half and
+    /// keeps it in place.
+    fn spare_snakes(&mut self, moves: &mut [Option<Move>]) -> Vec<(usize, Point)> {
+        let mut cuts = Vec::new();
+        let mut changed = true;
+
+        // a snake kept in place doesn't leave its tail, which may block
+        // snakes moving into it in turn
+        while changed {
+            changed = false;
+
+            for id in 0..moves.len() {
+                let Some(mv) = &moves[id] else { continue };
+
+                let blocked = self.snakes.iter().zip(moves.iter()).any(|(snake, other)| {
+                    snake.alive && other.is_none() && snake.body.contains(&mv.head)
+                });
+                if mv.death.is_none() && !blocked {
+                    continue;
+                }
+
+                let head_on = moves.iter().enumerate().any(|(other_id, other)| {
+                    other_id != id && matches!(other, Some(other) if other.head == mv.head)
+                });
+                let victim = self
+                    .snakes
+                    .iter()
+                    .find(|snake| snake.alive && snake.body.contains(&mv.head));
+
+                match victim {
+                    Some(victim) if !head_on && (victim.id == id || *victim.head() != mv.head) => {
+                        cuts.push((victim.id, mv.head.clone()));
+                        moves[id].as_mut().unwrap().death = None;
+                    }
+                    _ => {
+                        let length = self.snakes[id].body.len();
+                        self.shorten(id, length / 2);
+                        moves[id] = None;
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        cuts
+    }
+
+    /// Removes the segment on `cell` and everything behind it.
+    fn cut_snake(&mut self, id: usize, cell: &Point) {
+        if let Some(idx) = self.snakes[id].body.iter().position(|p| p == cell) {
+            self.shorten(id, idx + 1);
+        }
+
+        // the head that cut the snake is on the cell now
+        self.free.remove(cell);
+    }
+
+    /// Removes `count` segments from the tail of the snake.
+    fn shorten(&mut self, id: usize, count: usize) {
+        let body = &mut self.snakes[id].body;
+
+        for _ in 0..count.min(body.len().saturating_sub(1)) {
+            let tail = body.pop_front().unwrap();
+
+            if !body.contains(&tail) {
+                self.free.insert(tail);
+            }
+        }
+    }
+
+    /// Puts a wall on a random free cell away from the heads and the apple.
+    fn place_wall(&mut self) {
+        for _ in 0..100 {
+            if self.free.is_empty() {
+                return;
+            }
+
+            let point = self.free.sample(&mut self.rng).clone();
+            let near_head = self
+                .snakes
+                .iter()
+                .any(|snake| snake.alive && snake.head().manhattan_distance(&point) <= 3);
+            let near_portal = self.portals.iter().any(|(a, b)| {
+                a.manhattan_distance(&point) == 1 || b.manhattan_distance(&point) == 1
+            });
+
+            if !near_head && !near_portal && point != self.apple {
+                self.free.remove(&point);
+                self.items.retain(|item| item.position != point);
+                self.walls.push(point);
+                return;
+            }
+        }
+    }
+
+    fn kill(&mut self, id: usize) {
+        let snake = &mut self.snakes[id];
+        snake.alive = false;
+
+        for p in &snake.body {
+            self.free.insert(p.clone());
+        }
+    }
+
+    fn move_hazards(&mut self) {
+        let mut hazards = std::mem::take(&mut self.hazards);
+
+        for hazard in &mut hazards {
+            hazard.advance(|p| self.blocks_hazard(p, 0));
+        }
+
+        self.hazards = hazards;
+    }
+
+    fn forecast_hazards(&mut self) {
+        let mut hazards = self.hazards.clone();
+        self.hazard_forecast.clear();
+
+        for moves in 0..=hazard::FORECAST {
+            self.hazard_forecast
+                .push(hazards.iter().map(|h| h.position.clone()).collect());
+
+            for hazard in &mut hazards {
+                hazard.advance(|p| self.blocks_hazard(p, moves));
+            }
+        }
+    }
+
+    /// Hazards don't enter borders, walls, portals or snakes. The snakes are
+    /// expected to have made `moves` moves by then, leaving the cells at the
+    /// end of their tails.
+    fn blocks_hazard(&self, point: &Point, moves: usize) -> bool {
+        let left = |snake: &Snake| snake.body.iter().take(moves).any(|p| p == point);
+
+        point.x < 0
+            || point.x >= self.board.0
+            || point.y < 0
+            || point.y >= self.board.1
+            || self.is_occupied(point) && !self.snakes.iter().any(left)
+    }
+
+    /// Returns true if a snake entering `point` in `ticks` ticks from now
+    /// would run into a hazard.
+    pub fn hazard_threatens(&self, point: &Point, ticks: usize) -> bool {
+        self.hazard_at(point, ticks) || ticks > 0 && self.hazard_at(point, ticks - 1)
+    }
+
+    /// Returns true if a hazard is predicted to be on `point` in `ticks` ticks
+    /// from now. Past the end of the forecast hazards are expected to stay
+    /// where it leaves them.
+    pub fn hazard_at(&self, point: &Point, ticks: usize) -> bool {
+        let last = self.hazard_forecast.len().saturating_sub(1);
+
+        self.hazard_forecast
+            .get(ticks.min(last))
+            .is_some_and(|positions| positions.contains(point))
+    }
+
+    /// Sets the deaths of the moves. Snakes die running into a body that stays,
+    /// and of two heads meeting on a cell the shorter one dies, or both if
+    /// they are as long. A snake that dies anyway doesn't win a head-on.
+    fn resolve_collisions(&self, moves: &mut [Option<Move>]) {
+        let vacated = |p: &Point| {
+            self.snakes
+                .iter()
+                .zip(moves.iter())
+                .any(|(snake, mv)| matches!(mv, Some(mv) if !mv.eats) && snake.tail() == p)
+        };
+
+        let mut deaths: Vec<Option<Cause>> = moves
+            .iter()
+            .enumerate()
+            .map(|(id, mv)| match mv {
+                Some(Move {
+                    death: Some(death), ..
+                }) => Some(death.clone()),
+                Some(mv)
+                    if self.is_occupied(&mv.head)
+                        && !vacated(&mv.head)
+                        && !(self.is_ghost(id) && self.snakes[id].body.contains(&mv.head)) =>
+                {
+                    Some(self.collision_cause(id, &mv.head))
+                }
+                _ => None,
+            })
+            .collect();
+        let dying: Vec<bool> = deaths.iter().map(Option::is_some).collect();
+
+        for (id, mv) in moves.iter().enumerate() {
+            let Some(mv) = mv else { continue };
+            let length = self.snakes[id].body.len() + mv.eats as usize;
+
+            for (other_id, other) in moves.iter().enumerate() {
+                match other {
+                    Some(other) if other_id != id && other.head == mv.head && !dying[other_id] => {
+                        let other_length = self.snakes[other_id].body.len() + other.eats as usize;
+
+                        if length <= other_length && deaths[id].is_none() {
+                            deaths[id] = Some(Cause::Snake {
+                                cell: mv.head.clone(),
+                                id: other_id,
+                            });
+                        }
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        for (mv, death) in moves.iter_mut().zip(deaths) {
+            if let Some(mv) = mv {
+                mv.death = death;
+            }
+        }
+    }
+
+    /// Why the snake dies moving its head onto the occupied cell.
+    fn collision_cause(&self, id: usize, cell: &Point) -> Cause {
+        let body = &self.snakes[id].body;
+        if let Some(idx) = body.iter().position(|p| p == cell) {
+            return Cause::OwnBody {
+                cell: cell.clone(),
+                segment: body.len() - 1 - idx,
+            };
         }
-        if self.check_border(&mut new_head) || self.check_overlap(&new_head) {
-            return self.game_over();
+
+        match self
+            .snakes
+            .iter()
+            .find(|snake| snake.alive && snake.body.contains(cell))
+        {
+            Some(other) => Cause::Snake {
+                cell: cell.clone(),
+                id: other.id,
+            },
+            None => Cause::Wall(cell.clone()),
         }
-        self.snake.push_back(new_head);
-        if !self.check_apple() {
-            self.snake.pop_front();
+    }
+
+    /// Drops expired items and effects and may place a new item.
+    fn update_items(&mut self) {
+        let ticks = self.ticks;
+        self.items.retain(|item| item.expires >= ticks);
+        self.effects.retain(|effect| effect.until >= ticks);
+
+        if !self.spawn_items
+            || self.items.len() >= item::MAX_ITEMS
+            || !self.rng.gen_bool(item::SPAWN_CHANCE)
+            || self.free.is_empty()
+        {
+            return;
+        }
+
+        let kind = ItemKind::ALL[self.rng.gen_range(0..ItemKind::ALL.len())];
+        let position = self.free.sample(&mut self.rng).clone();
+
+        if position != self.apple && self.items.iter().all(|item| item.position != position) {
+            self.items.push(Item {
+                kind,
+                position,
+                expires: ticks + item::LIFETIME,
+            });
+        }
+    }
+
+    fn pick_up_items(&mut self) {
+        for id in 0..self.snakes.len() {
+            let snake = &self.snakes[id];
+            let Some(idx) = self
+                .items
+                .iter()
+                .position(|item| snake.alive && &item.position == snake.head())
+            else {
+                continue;
+            };
+
+            let kind = self.items.swap_remove(idx).kind;
+            let snake = &mut self.snakes[id];
+
+            match kind {
+                ItemKind::GoldenApple => snake.score += item::GOLDEN_APPLE_POINTS,
+                ItemKind::ShrinkPill => {
+                    for _ in 0..item::SHRINK_LENGTH.min(snake.body.len() - 1) {
+                        let tail = snake.body.pop_front().unwrap();
+
+                        if !snake.body.contains(&tail) {
+                            self.free.insert(tail);
+                        }
+                    }
+                }
+                ItemKind::SlowMotion | ItemKind::Ghost => (),
+            }
+
+            if let Some(duration) = kind.duration() {
+                self.effects.push(Effect {
+                    kind,
+                    snake: id,
+                    until: self.ticks + duration,
+                });
+            }
         }
-        self.dir = dir;
     }
 
+    /// Returns true if the point is covered by a snake in a way that would
+    /// still block it after the next move, that is anywhere except a tail.
     pub fn check_overlap(&self, new_head: &Point) -> bool {
-        for p in &self.snake {
-            if new_head == p && p != self.snake.front().unwrap() {
-                return true;
+        self.is_occupied(new_head)
+            && self
+                .snakes
+                .iter()
+                .all(|snake| !snake.alive || snake.tail() != new_head)
+    }
+
+    fn is_occupied(&self, point: &Point) -> bool {
+        self.free.index(point) == Some(None)
+    }
+
+    /// Returns where a snake at `point` ends up after moving in `dir`,
+    /// following portals and wrapping around the board when there are no
+    /// borders. Returns `None` if it would hit a border.
+    pub fn next_position(&self, point: &Point, dir: &Direction) -> Option<Point> {
+        let mut next = point.adjacent_point(dir);
+
+        if self.check_border(&mut next) {
+            return None;
+        }
+
+        if let Some(exit) = self.portal_exit(&next) {
+            next = exit.adjacent_point(dir);
+
+            if self.check_border(&mut next) {
+                return None;
             }
         }
 
-        false
+        Some(next)
+    }
+
+    /// Cells a snake at `point` can move to in one step.
+    pub fn neighbors<'a>(&'a self, point: &'a Point) -> impl Iterator<Item = Point> + 'a {
+        [
+            Direction::Left,
+            Direction::Up,
+            Direction::Right,
+            Direction::Down,
+        ]
+        .into_iter()
+        .filter_map(move |dir| self.next_position(point, &dir))
     }
 
-    fn check_border(&mut self, new_head: &mut Point) -> bool {
+    /// Returns the direction that takes a snake from `from` to `to` in one
+    /// step.
+    pub fn direction_between(&self, from: &Point, to: &Point) -> Option<Direction> {
+        [
+            Direction::Left,
+            Direction::Up,
+            Direction::Right,
+            Direction::Down,
+        ]
+        .into_iter()
+        .find(|dir| self.next_position(from, dir).as_ref() == Some(to))
+    }
+
+    fn portal_exit(&self, point: &Point) -> Option<&Point> {
+        self.portals.iter().find_map(|(a, b)| {
+            if a == point {
+                Some(b)
+            } else if b == point {
+                Some(a)
+            } else {
+                None
+            }
+        })
+    }
+
+    fn check_border(&self, new_head: &mut Point) -> bool {
         if self.borders
             && (new_head.x < 0
                 || new_head.x >= self.board.0
@@ -185,37 +1145,62 @@ impl Game {
         }
     }
 
-    fn check_apple(&mut self) -> bool {
-        if self.snake.back().unwrap() == &self.apple {
-            if self.snake.len() == self.board_size() as usize {
-                self.game_over();
-                return true;
-            }
-
-            self.gen_apple();
+    fn check_apple(&mut self) {
+        if self.gen_apple() {
+            return;
+        }
 
-            true
+        // no cell is left for the apple: the board is the player's, but for
+        // what walls and hazards take up, unless a rival covers part of it
+        self.state = if self.snakes.iter().skip(1).any(|snake| snake.alive) {
+            State::GameOver(Cause::Stalemate)
         } else {
-            false
-        }
+            State::Won
+        };
     }
 
-    fn gen_apple(&mut self) {
-        let mut rng = thread_rng();
+    /// Moves the apple to a random free cell no hazard is on. Returns false,
+    /// leaving the apple where it is, if there is no such cell.
+    pub fn gen_apple(&mut self) -> bool {
+        let under_hazard =
+            |p: &Point, hazards: &[Hazard]| hazards.iter().any(|hazard| hazard.position == *p);
 
-        self.apple = loop {
-            let x = rng.gen_range(0..self.board.0);
-            let y = rng.gen_range(0..self.board.1);
-            let apple = Point::new(x, y);
+        // hazards take up a few cells at most, so sampling nearly always
+        // finds one
+        let mut apple = None;
+        for _ in 0..100 {
+            if self.free.is_empty() {
+                break;
+            }
 
-            if !self.snake.contains(&apple) {
-                break apple;
+            let point = self.free.sample(&mut self.rng);
+            if !under_hazard(point, &self.hazards) {
+                apple = Some(point.clone());
+                break;
             }
         }
+
+        if apple.is_none() {
+            let cells: Vec<&Point> = self
+                .free
+                .cells
+                .iter()
+                .filter(|p| !under_hazard(p, &self.hazards))
+                .collect();
+            if cells.is_empty() {
+                return false;
+            }
+            apple = Some(cells[self.rng.gen_range(0..cells.len())].clone());
+        }
+
+        self.apple = apple.unwrap();
+        let apple = &self.apple;
+        self.items.retain(|item| &item.position != apple);
+        true
     }
 
-    fn game_over(&mut self) {
-        self.state = State::GameOver;
+    fn game_over(&mut self, cause: Cause) {
+        self.state = State::GameOver(cause);
     }
 
     pub fn is_running(&self) -> bool {
@@ -223,7 +1208,30 @@ impl Game {
     }
 
     pub fn is_game_over(&self) -> bool {
-        matches!(self.state, State::GameOver)
+        matches!(self.state, State::GameOver(_))
+    }
+
+    /// Returns why the game ended once it is over.
+    pub fn cause(&self) -> Option<&Cause> {
+        match &self.state {
+            State::GameOver(cause) => Some(cause),
+            _ => None,
+        }
+    }
+
+    /// Returns true once the player ate the apples the level asks for.
+    pub fn is_completed(&self) -> bool {
+        matches!(self.state, State::Completed)
+    }
+
+    /// Returns true once the player's snake fills the whole board.
+    pub fn is_won(&self) -> bool {
+        matches!(self.state, State::Won)
+    }
+
+    /// Returns true once the game is over, won or completed.
+    pub fn is_finished(&self) -> bool {
+        self.is_game_over() || self.is_won() || self.is_completed()
     }
 
     pub fn toggle_pause(&mut self) {
@@ -235,7 +1243,66 @@ impl Game {
     }
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+/// Cells that are not covered by a snake, a wall or a portal. Cells are kept
+/// in a vector for uniform sampling and every cell remembers its position in
+/// that vector so that insertion and removal take constant time.
+#[derive(Clone, Serialize, Deserialize)]
+struct FreeCells {
+    cells: Vec<Point>,
+    positions: Vec<Vec<Option<usize>>>,
+}
+
+impl FreeCells {
+    fn new(width: i32, height: i32) -> Self {
+        let mut cells = Vec::with_capacity((width * height) as usize);
+        let mut positions = vec![vec![None; width as usize]; height as usize];
+
+        for y in 0..height {
+            for x in 0..width {
+                positions[y as usize][x as usize] = Some(cells.len());
+                cells.push(Point::new(x, y));
+            }
+        }
+
+        Self { cells, positions }
+    }
+
+    /// Returns `None` if the point is out of the board, otherwise the position
+    /// of the point in the free list if it is free.
+    fn index(&self, point: &Point) -> Option<Option<usize>> {
+        self.positions
+            .get(point.y as usize)
+            .and_then(|row| row.get(point.x as usize))
+            .copied()
+    }
+
+    fn insert(&mut self, point: Point) {
+        if self.positions[point.y as usize][point.x as usize].is_none() {
+            self.positions[point.y as usize][point.x as usize] = Some(self.cells.len());
+            self.cells.push(point);
+        }
+    }
+
+    fn remove(&mut self, point: &Point) {
+        if let Some(idx) = self.positions[point.y as usize][point.x as usize].take() {
+            self.cells.swap_remove(idx);
+
+            if let Some(moved) = self.cells.get(idx) {
+                self.positions[moved.y as usize][moved.x as usize] = Some(idx);
+            }
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    fn sample(&self, rng: &mut impl Rng) -> &Point {
+        &self.cells[rng.gen_range(0..self.cells.len())]
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Point {
     pub x: i32,
     pub y: i32,
@@ -274,8 +1341,630 @@ impl Point {
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 enum State {
     Running,
     Paused,
-    GameOver,
+    GameOver(Cause),
+    Won,
+    Completed,
+}
+
+/// Why a game is over.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Cause {
+    /// The snake ran into the border from the cell.
+    Border(Point),
+    /// The snake ran into a wall on the cell.
+    Wall(Point),
+    /// The snake ran into its own body on the cell, `segment` segments behind
+    /// its head.
+    OwnBody { cell: Point, segment: usize },
+    /// The snake ran into the snake with the id on the cell, or their heads
+    /// met there.
+    Snake { cell: Point, id: usize },
+    /// The snake ran into a hazard on the cell.
+    Hazard(Point),
+    /// The time of a time attack game ran out.
+    TimeUp,
+    /// No cell was left for the apple while other snakes covered part of the
+    /// board.
+    Stalemate,
+}
+
+impl Cause {
+    /// The cell the snake crashed on, if it crashed.
+    pub fn cell(&self) -> Option<&Point> {
+        match self {
+            Cause::Border(cell)
+            | Cause::Wall(cell)
+            | Cause::OwnBody { cell, .. }
+            | Cause::Snake { cell, .. }
+            | Cause::Hazard(cell) => Some(cell),
+            Cause::TimeUp | Cause::Stalemate => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Cause::Border(_) => "border",
+            Cause::Wall(_) => "wall",
+            Cause::OwnBody { .. } => "own body",
+            Cause::Snake { .. } => "other snake",
+            Cause::Hazard(_) => "hazard",
+            Cause::TimeUp => "time up",
+            Cause::Stalemate => "stalemate",
+        }
+    }
+}
+
+impl fmt::Display for Cause {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Cause::Border(_) => write!(f, "the snake hit the border"),
+            Cause::Wall(_) => write!(f, "the snake hit a wall"),
+            Cause::OwnBody { segment, .. } => {
+                write!(f, "the snake bit its own body {} segments back", segment)
+            }
+            Cause::Snake { .. } => write!(f, "the snake ran into another snake"),
+            Cause::Hazard(_) => write!(f, "the snake ran into a hazard"),
+            Cause::TimeUp => write!(f, "the time ran out"),
+            Cause::Stalemate => write!(f, "the board is full"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Removes every cell of a `width` × `height` board but `last`, in an
+    /// order that moves cells around in the free list.
+    fn all_but(width: i32, height: i32, last: &Point) -> FreeCells {
+        let mut free = FreeCells::new(width, height);
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+
+        while free.cells.len() > 1 {
+            let p = free.sample(&mut rng).clone();
+            if p != *last {
+                free.remove(&p);
+            }
+        }
+
+        free
+    }
+
+    #[test]
+    fn last_free_cell_is_always_sampled() {
+        for y in 0..4 {
+            for x in 0..5 {
+                let last = Point::new(x, y);
+                let free = all_but(5, 4, &last);
+                let mut rng = ChaCha8Rng::seed_from_u64(x as u64 * 10 + y as u64);
+
+                assert_eq!(free.index(&last), Some(Some(0)));
+                for _ in 0..20 {
+                    assert_eq!(free.sample(&mut rng), &last);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn apple_goes_to_last_free_cell() {
+        let options = Options {
+            width: 4,
+            height: 4,
+            head_x: 0,
+            head_y: 0,
+            length: 1,
+            seed: Some(3),
+            ..Options::default()
+        };
+        let mut game = Game::new(&options);
+
+        // a second snake winding through everything but the last cell
+        let body: VecDeque<Point> = (1..15)
+            .map(|idx| {
+                let (x, y) = (idx % 4, idx / 4);
+                Point::new(if y % 2 == 0 { x } else { 3 - x }, y)
+            })
+            .collect();
+        game.add_snake(body, Direction::Right).unwrap();
+
+        for _ in 0..10 {
+            game.gen_apple();
+            assert_eq!(game.apple, Point::new(0, 3));
+        }
+    }
+
+    #[test]
+    fn full_board_is_a_stalemate_while_a_rival_covers_it() {
+        let options = Options {
+            width: 4,
+            height: 4,
+            head_x: 0,
+            head_y: 0,
+            length: 1,
+            seed: Some(0),
+            ..Options::default()
+        };
+        let mut game = Game::new(&options);
+
+        // a rival chasing its tail around everything but the first row's
+        // first two cells
+        let body = [
+            (2, 0),
+            (3, 0),
+            (3, 1),
+            (3, 2),
+            (3, 3),
+            (2, 3),
+            (1, 3),
+            (0, 3),
+            (0, 2),
+            (0, 1),
+            (1, 1),
+            (1, 2),
+            (2, 2),
+            (2, 1),
+        ];
+        let body = body.into_iter().map(|(x, y)| Point::new(x, y)).collect();
+        game.add_snake(body, Direction::Up).unwrap();
+        game.apple = Point::new(1, 0);
+        game.move_snake(Direction::Right);
+
+        assert_eq!(game.cause(), Some(&Cause::Stalemate));
+        assert_eq!(game.snake().len(), 2);
+    }
+
+    #[test]
+    fn board_is_won_when_only_a_hazard_keeps_a_cell() {
+        let options = Options {
+            width: 4,
+            height: 4,
+            head_x: 0,
+            head_y: 0,
+            length: 1,
+            seed: Some(0),
+            ..Options::default()
+        };
+        let mut game = Game::new(&options);
+
+        // walls on everything but the first row's first three cells, and a
+        // hazard that never moves on the third
+        for y in 0..4 {
+            for x in 0..4 {
+                if y > 0 || x == 3 {
+                    let wall = Point::new(x, y);
+                    game.free.remove(&wall);
+                    game.walls.push(wall);
+                }
+            }
+        }
+        game.hazards = vec![Hazard::path(&[Point::new(2, 0)]).unwrap()];
+
+        // the apple never goes under the hazard
+        for _ in 0..20 {
+            assert!(game.gen_apple());
+            assert_eq!(game.apple, Point::new(1, 0));
+        }
+
+        game.move_snake(Direction::Right);
+        assert!(game.is_won());
+    }
+
+    /// A 12 × 12 board with the player out of the way in the bottom row.
+    fn arena(mode: Mode) -> Game {
+        let options = Options {
+            width: 12,
+            height: 12,
+            head_x: 2,
+            head_y: 11,
+            mode,
+            seed: Some(0),
+            ..Options::default()
+        };
+
+        Game::new(&options)
+    }
+
+    /// Adds a snake given from its tail to its head.
+    fn add(game: &mut Game, cells: &[(i32, i32)], dir: Direction) -> usize {
+        let body = cells.iter().map(|&(x, y)| Point::new(x, y)).collect();
+        game.add_snake(body, dir).unwrap()
+    }
+
+    /// Moves the player right and every other snake on.
+    fn tick(game: &mut Game, apple: (i32, i32)) {
+        game.apple = Point::new(apple.0, apple.1);
+        game.step(&[Direction::Right]);
+    }
+
+    #[test]
+    fn longer_snake_wins_head_on() {
+        let mut game = arena(Mode::Endless);
+        let short = add(&mut game, &[(3, 5), (4, 5), (5, 5)], Direction::Right);
+        let long = add(
+            &mut game,
+            &[(10, 5), (9, 5), (8, 5), (7, 5)],
+            Direction::Left,
+        );
+        tick(&mut game, (0, 0));
+
+        assert!(!game.snakes[short].alive);
+        assert!(game.snakes[long].alive);
+        assert_eq!(game.snakes[long].head(), &Point::new(6, 5));
+    }
+
+    #[test]
+    fn snakes_as_long_both_die_head_on() {
+        let mut game = arena(Mode::Endless);
+        let a = add(&mut game, &[(3, 5), (4, 5), (5, 5)], Direction::Right);
+        let b = add(&mut game, &[(9, 5), (8, 5), (7, 5)], Direction::Left);
+        tick(&mut game, (0, 0));
+
+        assert!(!game.snakes[a].alive);
+        assert!(!game.snakes[b].alive);
+        assert!(game.is_running());
+    }
+
+    #[test]
+    fn head_into_body_dies() {
+        let mut game = arena(Mode::Endless);
+        let a = add(&mut game, &[(3, 5), (4, 5), (5, 5)], Direction::Right);
+        let b = add(&mut game, &[(6, 7), (6, 6), (6, 5), (6, 4)], Direction::Up);
+        tick(&mut game, (0, 0));
+
+        assert!(!game.snakes[a].alive);
+        assert!(game.snakes[b].alive);
+    }
+
+    #[test]
+    fn player_into_body_names_the_snake() {
+        let mut game = arena(Mode::Endless);
+        let other = add(
+            &mut game,
+            &[(4, 11), (3, 11), (3, 10), (3, 9)],
+            Direction::Up,
+        );
+        tick(&mut game, (0, 0));
+
+        assert_eq!(
+            game.cause(),
+            Some(&Cause::Snake {
+                cell: Point::new(3, 11),
+                id: other
+            })
+        );
+    }
+
+    #[test]
+    fn longer_snake_gets_the_apple_both_reach() {
+        let mut game = arena(Mode::Endless);
+        let short = add(&mut game, &[(3, 5), (4, 5), (5, 5)], Direction::Right);
+        let long = add(
+            &mut game,
+            &[(10, 5), (9, 5), (8, 5), (7, 5)],
+            Direction::Left,
+        );
+        tick(&mut game, (6, 5));
+
+        assert!(!game.snakes[short].alive);
+        assert_eq!(game.snakes[long].apples, 1);
+        assert_eq!(game.snakes[long].body.len(), 5);
+    }
+
+    #[test]
+    fn head_may_follow_a_tail_that_moves_on() {
+        let mut game = arena(Mode::Endless);
+        let a = add(&mut game, &[(3, 5), (4, 5), (5, 5)], Direction::Right);
+        let b = add(&mut game, &[(6, 5), (6, 6), (6, 7)], Direction::Down);
+        tick(&mut game, (0, 0));
+
+        assert!(game.snakes[a].alive);
+        assert!(game.snakes[b].alive);
+    }
+
+    #[test]
+    fn tail_of_a_snake_eating_stays() {
+        let mut game = arena(Mode::Endless);
+        let a = add(&mut game, &[(3, 5), (4, 5), (5, 5)], Direction::Right);
+        let b = add(&mut game, &[(6, 5), (6, 6), (6, 7)], Direction::Down);
+        tick(&mut game, (6, 8));
+
+        assert!(!game.snakes[a].alive);
+        assert_eq!(game.snakes[b].body.len(), 4);
+    }
+
+    #[test]
+    fn zen_cuts_the_body_run_into() {
+        let mut game = arena(Mode::Zen);
+        let a = add(&mut game, &[(3, 5), (4, 5), (5, 5)], Direction::Right);
+        let b = add(&mut game, &[(6, 7), (6, 6), (6, 5), (6, 4)], Direction::Up);
+        tick(&mut game, (0, 0));
+
+        assert!(game.snakes[a].alive);
+        assert_eq!(game.snakes[a].head(), &Point::new(6, 5));
+        let body: Vec<Point> = game.snakes[b].body.iter().cloned().collect();
+        assert_eq!(body, [Point::new(6, 4), Point::new(6, 3)]);
+    }
+
+    #[test]
+    fn snake_dying_anyway_doesnt_win_head_on() {
+        let mut game = arena(Mode::Endless);
+        // a ghost turning back into its own body
+        let ghost = add(
+            &mut game,
+            &[(7, 2), (6, 2), (6, 3), (7, 3), (7, 4), (6, 4)],
+            Direction::Left,
+        );
+        game.effects.push(Effect {
+            kind: ItemKind::Ghost,
+            snake: ghost,
+            until: 10,
+        });
+        // a longer snake running into the ghost's body on the same cell
+        let long = add(
+            &mut game,
+            &[(2, 6), (2, 5), (2, 4), (2, 3), (3, 3), (4, 3), (5, 3)],
+            Direction::Right,
+        );
+        game.apple = Point::new(0, 0);
+        game.step(&[Direction::Right, Direction::Up]);
+
+        assert!(!game.snakes[long].alive);
+        assert!(game.snakes[ghost].alive);
+        assert_eq!(game.snakes[ghost].head(), &Point::new(6, 3));
+    }
+
+    #[test]
+    fn portal_teleports_the_head_and_the_body_follows() {
+        let level = Level::parse(
+            "[map]\n\
+             ..........\n\
+             ...>.a....\n\
+             ..........\n\
+             .......a..\n\
+             ..........\n",
+        )
+        .unwrap();
+        let mut options = Options {
+            seed: Some(0),
+            ..Options::default()
+        };
+        options.set_level(level);
+        let mut game = Game::new(&options);
+        game.apple = Point::new(0, 4);
+
+        for _ in 0..3 {
+            game.move_snake(Direction::Right);
+        }
+
+        let body: Vec<Point> = game.snake().iter().cloned().collect();
+        assert!(game.is_running());
+        assert_eq!(game.dir(), &Direction::Right);
+        assert_eq!(body, [Point::new(4, 1), Point::new(8, 3), Point::new(9, 3)]);
+    }
+
+    /// A board without borders with a snake of `length` heading right.
+    fn open_board(length: i32) -> Game {
+        let options = Options {
+            head_x: 8,
+            length,
+            borders: false,
+            seed: Some(0),
+            ..Options::default()
+        };
+        let mut game = Game::new(&options);
+        game.apple = Point::new(0, 19);
+
+        game
+    }
+
+    /// Puts an item in front of the player's head.
+    fn put_ahead(game: &mut Game, kind: ItemKind) {
+        let head = game.snake().back().unwrap().clone();
+        game.items.push(Item {
+            kind,
+            position: Point::new(head.x + 1, head.y),
+            expires: u64::MAX,
+        });
+    }
+
+    #[test]
+    fn shrink_pill_cuts_the_tail() {
+        let mut game = open_board(6);
+        put_ahead(&mut game, ItemKind::ShrinkPill);
+        game.move_snake(Direction::Right);
+
+        assert!(game.items.is_empty());
+        assert_eq!(game.snake().len(), 6 - item::SHRINK_LENGTH);
+    }
+
+    #[test]
+    fn slow_motion_slows_the_clock_too() {
+        let mut game = open_board(3);
+        game.time_limit = Some(1000);
+        put_ahead(&mut game, ItemKind::SlowMotion);
+        game.move_snake(Direction::Right);
+
+        let duration = ItemKind::SlowMotion.duration().unwrap();
+        assert_eq!(
+            game.effect_remaining(0, ItemKind::SlowMotion),
+            Some(duration + 1)
+        );
+
+        while game.effect_remaining(0, ItemKind::SlowMotion).is_some() {
+            let (ticks, head) = (game.ticks, game.snake().back().unwrap().x);
+            let remaining = game.remaining_ticks();
+
+            game.move_snake(Direction::Right);
+            game.move_snake(Direction::Right);
+
+            assert_eq!(game.ticks, ticks + 1);
+            assert_eq!(game.snake().back().unwrap().x, (head + 1) % 30);
+            assert_eq!(game.remaining_ticks(), remaining.map(|ticks| ticks - 1));
+        }
+
+        // the effect wore off
+        let ticks = game.ticks;
+        game.move_snake(Direction::Right);
+        assert_eq!(game.ticks, ticks + 1);
+        assert!(game.is_running());
+    }
+
+    #[test]
+    fn ghost_passes_through_its_own_body_until_it_wears_off() {
+        let turn = [Direction::Down, Direction::Left, Direction::Up];
+
+        let mut game = open_board(5);
+        put_ahead(&mut game, ItemKind::Ghost);
+        game.move_snake(Direction::Right);
+        for dir in turn.clone() {
+            game.move_snake(dir);
+        }
+        assert!(game.is_running());
+
+        while game.effect_remaining(0, ItemKind::Ghost).is_some() {
+            game.move_snake(Direction::Up);
+        }
+        game.move_snake(Direction::Right);
+        for dir in turn {
+            game.move_snake(dir);
+        }
+        assert!(matches!(game.cause(), Some(Cause::OwnBody { .. })));
+    }
+
+    #[test]
+    fn items_disappear_after_their_lifetime() {
+        let mut game = open_board(3);
+        game.items.push(Item {
+            kind: ItemKind::GoldenApple,
+            position: Point::new(10, 10),
+            expires: item::LIFETIME,
+        });
+
+        for _ in 0..item::LIFETIME {
+            game.move_snake(Direction::Right);
+        }
+        assert_eq!(game.items.len(), 1);
+
+        game.move_snake(Direction::Right);
+        assert!(game.items.is_empty());
+    }
+
+    #[test]
+    fn time_attack_ends_when_the_time_runs_out() {
+        let options = Options {
+            mode: Mode::TimeAttack,
+            time_limit: 1,
+            speed: 5.0,
+            seed: Some(0),
+            ..Options::default()
+        };
+        let mut game = Game::new(&options);
+        game.apple = Point::new(4, 3);
+
+        for _ in 0..4 {
+            game.move_snake(Direction::Right);
+        }
+        assert!(game.is_running());
+        assert_eq!(game.remaining_ticks(), Some(1));
+
+        game.move_snake(Direction::Right);
+        assert_eq!(game.cause(), Some(&Cause::TimeUp));
+        assert_eq!(game.score(), 1);
+    }
+
+    #[test]
+    fn survival_scores_ticks_while_walls_appear() {
+        let mut game = open_board(3);
+        game.mode = Mode::Survival;
+        game.apple = Point::new(9, 3);
+
+        for _ in 1..mode::SURVIVAL_WALL_INTERVAL {
+            game.move_snake(Direction::Right);
+        }
+        assert!(game.walls.is_empty());
+
+        game.move_snake(Direction::Right);
+        assert_eq!(game.walls.len(), 1);
+        assert_eq!(game.snakes[0].apples, 1);
+        assert_eq!(game.score(), mode::SURVIVAL_WALL_INTERVAL as usize);
+    }
+
+    #[test]
+    fn zen_cuts_a_snake_running_into_itself() {
+        let options = Options {
+            mode: Mode::Zen,
+            head_x: 8,
+            length: 5,
+            seed: Some(0),
+            ..Options::default()
+        };
+        let mut game = Game::new(&options);
+        game.apple = Point::new(0, 19);
+
+        for dir in [Direction::Down, Direction::Left, Direction::Up] {
+            game.move_snake(dir);
+        }
+
+        let body: Vec<Point> = game.snake().iter().cloned().collect();
+        assert!(game.is_running());
+        assert_eq!(
+            body,
+            [
+                Point::new(8, 3),
+                Point::new(8, 4),
+                Point::new(7, 4),
+                Point::new(7, 3)
+            ]
+        );
+        assert_eq!(game.score(), 0);
+    }
+
+    #[test]
+    fn same_seed_gives_same_apples() {
+        let options = Options {
+            seed: Some(42),
+            ..Options::default()
+        };
+        let apples = |mut game: Game| -> Vec<Point> {
+            (0..100)
+                .map(|_| {
+                    game.gen_apple();
+                    game.apple.clone()
+                })
+                .collect()
+        };
+
+        assert_eq!(apples(Game::new(&options)), apples(Game::new(&options)));
+    }
+
+    #[test]
+    fn golden_apples_do_not_count_towards_the_goal() {
+        let options = Options {
+            seed: Some(0),
+            goal: Some(1),
+            ..Options::default()
+        };
+        let mut game = Game::new(&options);
+        let dir = game.dir().clone();
+        let position = game.next_position(game.snake().back().unwrap(), &dir);
+        let position = position.unwrap();
+
+        game.apple = Point::new(0, 0);
+        game.items.push(Item {
+            kind: ItemKind::GoldenApple,
+            position,
+            expires: u64::MAX,
+        });
+        game.move_snake(dir);
+
+        assert_eq!(game.score(), item::GOLDEN_APPLE_POINTS);
+        assert_eq!(game.snakes[0].apples, 0);
+        assert!(!game.is_completed());
+    }
 }
@@ -0,0 +1,182 @@
+use crate::game::{Direction, Point};
+use serde::{Deserialize, Serialize};
+
+/// Ticks ahead the game predicts the positions of hazards for.
+pub const FORECAST: usize = 32;
+
+/// How a hazard moves around the board.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Patrol {
+    /// Moves straight on and turns back when something blocks the way.
+    Bounce { direction: Direction },
+    /// Follows a route cell by cell, waiting while the next cell is blocked.
+    /// A looped route starts over once the end is reached, any other route is
+    /// walked back.
+    Route {
+        cells: Vec<Point>,
+        idx: usize,
+        forward: bool,
+        looped: bool,
+    },
+}
+
+/// An enemy that patrols the board and kills snakes on contact.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hazard {
+    pub position: Point,
+    pub patrol: Patrol,
+}
+
+impl Hazard {
+    pub fn bounce(position: Point, direction: Direction) -> Self {
+        Self {
+            position,
+            patrol: Patrol::Bounce { direction },
+        }
+    }
+
+    /// Circles clockwise around a `size` x `size` square whose top left corner
+    /// is `corner`, starting from the corner.
+    pub fn circle(corner: Point, size: i32) -> Self {
+        let size = size.max(2) - 1;
+        let waypoints = [
+            corner.clone(),
+            Point::new(corner.x + size, corner.y),
+            Point::new(corner.x + size, corner.y + size),
+            Point::new(corner.x, corner.y + size),
+            corner,
+        ];
+
+        Hazard::path(&waypoints).unwrap()
+    }
+
+    /// Walks the straight lines between the waypoints. The route is looped if
+    /// the last waypoint is the first one.
+    pub fn path(waypoints: &[Point]) -> Result<Self, String> {
+        let mut cells = vec![waypoints.first().ok_or("the path is empty")?.clone()];
+
+        for target in &waypoints[1..] {
+            let mut current = cells.last().unwrap().clone();
+
+            if current.x != target.x && current.y != target.y {
+                return Err(format!(
+                    "({}, {}) and ({}, {}) aren't on the same row or column",
+                    current.x, current.y, target.x, target.y
+                ));
+            }
+
+            while current != *target {
+                current.x += (target.x - current.x).signum();
+                current.y += (target.y - current.y).signum();
+                cells.push(current.clone());
+            }
+        }
+
+        let looped = cells.len() > 1 && cells.first() == cells.last();
+        if looped {
+            cells.pop();
+        }
+
+        Ok(Self {
+            position: cells[0].clone(),
+            patrol: Patrol::Route {
+                cells,
+                idx: 0,
+                forward: true,
+                looped,
+            },
+        })
+    }
+
+    /// Cells the hazard passes through, starting from its current position.
+    pub fn cells(&self) -> Vec<Point> {
+        match &self.patrol {
+            Patrol::Bounce { .. } => vec![self.position.clone()],
+            Patrol::Route { cells, .. } => cells.clone(),
+        }
+    }
+
+    /// Corners of the route, the way it's written in a level file. The first
+    /// corner is repeated at the end of a looped route.
+    pub fn waypoints(&self) -> Vec<Point> {
+        let Patrol::Route { cells, looped, .. } = &self.patrol else {
+            return vec![self.position.clone()];
+        };
+
+        let mut waypoints = vec![cells[0].clone()];
+
+        for window in cells.windows(3) {
+            if window[0].direction_to(&window[1]) != window[1].direction_to(&window[2]) {
+                waypoints.push(window[1].clone());
+            }
+        }
+
+        if cells.len() > 1 {
+            waypoints.push(cells.last().unwrap().clone());
+        }
+
+        if *looped {
+            waypoints.push(cells[0].clone());
+        }
+
+        waypoints
+    }
+
+    /// Moves the hazard one cell. `blocked` tells whether the hazard can't
+    /// enter a cell.
+    pub fn advance(&mut self, blocked: impl Fn(&Point) -> bool) {
+        match &mut self.patrol {
+            Patrol::Bounce { direction } => {
+                let next = self.position.adjacent_point(direction);
+
+                if !blocked(&next) {
+                    self.position = next;
+                    return;
+                }
+
+                *direction = Direction::opposite_dir(direction);
+                let back = self.position.adjacent_point(direction);
+
+                if !blocked(&back) {
+                    self.position = back;
+                }
+            }
+            Patrol::Route {
+                cells,
+                idx,
+                forward,
+                looped,
+            } => {
+                if cells.len() < 2 {
+                    return;
+                }
+
+                let mut next = *idx;
+                let mut next_forward = *forward;
+
+                if *looped {
+                    next = (next + 1) % cells.len();
+                } else {
+                    if next == cells.len() - 1 {
+                        next_forward = false;
+                    } else if next == 0 {
+                        next_forward = true;
+                    }
+
+                    if next_forward {
+                        next += 1;
+                    } else {
+                        next -= 1;
+                    }
+                }
+
+                if !blocked(&cells[next]) {
+                    *idx = next;
+                    *forward = next_forward;
+                    self.position = cells[next].clone();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use crate::game::Game;
+use crate::mode::Mode;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Scores kept for each mode.
+pub const TABLE_SIZE: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HighScore {
+    pub score: usize,
+    pub length: usize,
+    pub ticks: u64,
+    /// Seconds since the Unix epoch when the game ended.
+    pub time: u64,
+    /// Whether the game was rewound.
+    #[serde(default)]
+    pub assisted: bool,
+}
+
+impl HighScore {
+    pub fn new(game: &Game) -> Self {
+        Self {
+            score: game.score(),
+            length: game.snake().len(),
+            ticks: game.ticks,
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |time| time.as_secs()),
+            assisted: game.assisted,
+        }
+    }
+}
+
+/// The best scores of every mode, stored in the user's data directory.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct HighScores {
+    tables: BTreeMap<String, Vec<HighScore>>,
+}
+
+impl HighScores {
+    pub fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("snake").join("highscores.json"))
+    }
+
+    /// Reads the high scores, starting over if there are none yet or the
+    /// file can't be read.
+    pub fn load() -> Self {
+        HighScores::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        let path = HighScores::path().ok_or("no data directory")?;
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+
+        Ok(())
+    }
+
+    pub fn table(&self, mode: Mode) -> &[HighScore] {
+        self.tables.get(mode.name()).map_or(&[], Vec::as_slice)
+    }
+
+    /// Adds the score to the table of the mode and returns its rank starting
+    /// from 0, or `None` if it isn't good enough for the table.
+    pub fn insert(&mut self, mode: Mode, entry: HighScore) -> Option<usize> {
+        let table = self.tables.entry(mode.name().to_string()).or_default();
+        let rank = table
+            .iter()
+            .position(|other| entry.score > other.score)
+            .unwrap_or(table.len());
+
+        if rank >= TABLE_SIZE {
+            return None;
+        }
+
+        table.insert(rank, entry);
+        table.truncate(TABLE_SIZE);
+
+        Some(rank)
+    }
+
+    /// Takes `previous` out of the table of the mode if it is still there and
+    /// adds the score in its place, for a run rewound after it was recorded.
+    pub fn replace(&mut self, mode: Mode, previous: &HighScore, entry: HighScore) -> Option<usize> {
+        if let Some(table) = self.tables.get_mut(mode.name()) {
+            if let Some(idx) = table.iter().position(|other| other == previous) {
+                table.remove(idx);
+            }
+        }
+
+        self.insert(mode, entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Direction, Options};
+    use crate::rewind::History;
+
+    /// Plays until the snake runs into the border, keeping its states.
+    fn run_into_border(game: &mut Game, history: &mut History) {
+        while game.is_running() {
+            history.push(game);
+            game.move_snake(Direction::Right);
+        }
+    }
+
+    #[test]
+    fn rewound_run_replaces_its_high_score() {
+        let options = Options {
+            seed: Some(0),
+            ..Options::default()
+        };
+        let mut game = Game::new(&options);
+        let mut history = History::new(10);
+        let mut high_scores = HighScores::default();
+
+        run_into_border(&mut game, &mut history);
+        let first = HighScore::new(&game);
+        high_scores.insert(game.mode, first.clone());
+
+        let mut game = history.rewind(5).unwrap();
+        game.assisted = true;
+        run_into_border(&mut game, &mut history);
+        high_scores.replace(game.mode, &first, HighScore::new(&game));
+
+        let table = high_scores.table(game.mode);
+        assert_eq!(table.len(), 1);
+        assert!(table[0].assisted);
+    }
+}
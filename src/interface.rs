@@ -1,13 +1,27 @@
+use crate::bot::Bot;
+use crate::broadcast::Broadcaster;
+use crate::cast::{Cast, CastWriter};
 use crate::event::{Event, EventHandle};
-use crate::game::{self, Direction, Game};
+use crate::game::{self, Cause, Direction, Game, Point};
+use crate::highscore::{HighScore, HighScores};
+use crate::item::{Item, ItemKind};
+use crate::mode::Mode;
+use crate::replay::Replay;
+use crate::rewind::History;
+use crate::rival::Rival;
+use crate::save::SavedGame;
 use crate::solver::Solver;
+use crate::stats;
 use crossterm::event::KeyCode;
 use crossterm::execute;
 use crossterm::terminal::{
     disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
 };
+use std::io::Write;
+use std::time::Duration;
 use std::{error::Error, io};
-use tui::layout::Rect;
+use tui::backend::Backend;
+use tui::layout::{Alignment, Rect};
 use tui::style::Color;
 use tui::style::Style;
 use tui::text::{Span, Spans, Text};
@@ -15,149 +29,674 @@ use tui::widgets::Paragraph;
 use tui::{
     backend::CrosstermBackend,
     widgets::{Block, Borders},
-    Terminal,
+    Frame, Terminal,
 };
 
-pub fn run_ui(options: game::Options) -> Result<(), Box<dyn Error>> {
+pub(crate) type Grid = Vec<Vec<Span<'static>>>;
+
+const APPLE_CHAR: &str = "🍎";
+const SNAKE_CHAR: &str = "██";
+const WALL_CHAR: &str = "▓▓";
+pub(crate) const PORTAL_CHAR: &str = "◖◗";
+const HAZARD_CHAR: &str = "👾";
+
+/// Colors of the portal pairs, repeated when there are more pairs.
+const PORTAL_COLORS: [Color; 4] = [
+    Color::LightBlue,
+    Color::LightMagenta,
+    Color::LightGreen,
+    Color::LightRed,
+];
+
+/// Head and body colors of the snakes, the first one being the player's.
+const SNAKE_COLORS: [(Color, Color); 6] = [
+    (Color::Blue, Color::Yellow),
+    (Color::Magenta, Color::LightMagenta),
+    (Color::Cyan, Color::LightCyan),
+    (Color::Green, Color::LightGreen),
+    (Color::Red, Color::LightRed),
+    (Color::Gray, Color::White),
+];
+
+pub(crate) fn snake_colors(id: usize) -> (Color, Color) {
+    if id == 0 {
+        SNAKE_COLORS[0]
+    } else {
+        SNAKE_COLORS[1 + (id - 1) % (SNAKE_COLORS.len() - 1)]
+    }
+}
+
+fn new_rivals(game: &Game, options: &game::Options) -> Vec<Rival> {
+    game.snakes
+        .iter()
+        .skip(1)
+        .map(|snake| Rival::new(game, snake.id, options.rival_level))
+        .collect()
+}
+
+pub(crate) fn setup_terminal() -> Result<Terminal<CrosstermBackend<io::Stdout>>, Box<dyn Error>> {
+    setup_terminal_with(io::stdout())
+}
+
+/// Sets up the terminal to draw through `out`, which has to end up on stdout.
+pub(crate) fn setup_terminal_with<W: Write>(
+    out: W,
+) -> Result<Terminal<CrosstermBackend<W>>, Box<dyn Error>> {
     enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    execute!(io::stdout(), EnterAlternateScreen)?;
+    let backend = CrosstermBackend::new(out);
+
+    Ok(Terminal::new(backend)?)
+}
+
+pub(crate) fn restore_terminal() -> Result<(), Box<dyn Error>> {
+    disable_raw_mode()?;
+    execute!(io::stdout(), LeaveAlternateScreen)?;
+
+    Ok(())
+}
+
+pub(crate) fn new_grid(board: (i32, i32)) -> Grid {
+    vec![vec![Span::raw("  "); board.0 as usize]; board.1 as usize]
+}
+
+pub(crate) fn draw_apple(grid: &mut Grid, apple: &Point) {
+    grid[apple.y as usize][apple.x as usize] =
+        Span::styled(APPLE_CHAR, Style::default().fg(Color::Red));
+}
+
+pub(crate) fn draw_level(grid: &mut Grid, walls: &[Point], portals: &[(Point, Point)]) {
+    for wall in walls {
+        grid[wall.y as usize][wall.x as usize] =
+            Span::styled(WALL_CHAR, Style::default().fg(Color::Gray));
+    }
+
+    for (idx, (a, b)) in portals.iter().enumerate() {
+        let style = Style::default().fg(PORTAL_COLORS[idx % PORTAL_COLORS.len()]);
+        for p in [a, b] {
+            grid[p.y as usize][p.x as usize] = Span::styled(PORTAL_CHAR, style);
+        }
+    }
+}
+
+pub(crate) fn draw_hazards<'a>(grid: &mut Grid, hazards: impl IntoIterator<Item = &'a Point>) {
+    for p in hazards {
+        grid[p.y as usize][p.x as usize] =
+            Span::styled(HAZARD_CHAR, Style::default().fg(Color::LightRed));
+    }
+}
+
+/// Marks the cell the snake crashed on.
+pub(crate) fn draw_crash(grid: &mut Grid, cell: &Point) {
+    let span = &mut grid[cell.y as usize][cell.x as usize];
+    span.style = span.style.bg(Color::Red);
+}
+
+fn item_glyph(kind: ItemKind) -> (&'static str, Color) {
+    match kind {
+        ItemKind::GoldenApple => ("⭐", Color::Yellow),
+        ItemKind::ShrinkPill => ("💊", Color::Magenta),
+        ItemKind::SlowMotion => ("🐌", Color::Cyan),
+        ItemKind::Ghost => ("👻", Color::White),
+    }
+}
+
+pub(crate) fn draw_items(grid: &mut Grid, items: &[Item]) {
+    for item in items {
+        let (glyph, color) = item_glyph(item.kind);
+        grid[item.position.y as usize][item.position.x as usize] =
+            Span::styled(glyph, Style::default().fg(color));
+    }
+}
+
+/// Lists the effects active on the player with their remaining ticks.
+fn effect_spans(game: &Game) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+
+    for (kind, name) in [(ItemKind::SlowMotion, "Slow"), (ItemKind::Ghost, "Ghost")] {
+        if let Some(remaining) = game.effect_remaining(0, kind) {
+            let (_, color) = item_glyph(kind);
+            spans.push(Span::styled(
+                format!("{} {}", name, remaining),
+                Style::default().fg(color),
+            ));
+            spans.push(Span::raw(" | "));
+        }
+    }
+
+    spans
+}
+
+/// Fields shown in the status line of the modes other than endless.
+fn mode_spans(game: &Game, speed: f64) -> Vec<Span<'static>> {
+    let mut fields = match game.mode {
+        Mode::Endless => vec![],
+        Mode::TimeAttack => {
+            let seconds = (game.remaining_ticks().unwrap_or(0) as f64 / speed).ceil();
+            vec![
+                format!("Time {}s", seconds),
+                format!("Score {}", game.score()),
+            ]
+        }
+        Mode::Survival => vec![
+            format!("Survived {}", game.score()),
+            format!("Walls {}", game.walls.len()),
+        ],
+        Mode::Zen => vec![
+            format!("Score {}", game.score()),
+            format!("Length {}", game.snake().len()),
+        ],
+    };
+
+    if let Some(goal) = game.goal {
+        fields.insert(0, format!("Apples {}/{}", game.snakes[0].apples, goal));
+    }
+
+    fields
+        .into_iter()
+        .flat_map(|field| {
+            [
+                Span::styled(field, Style::default().fg(Color::Cyan)),
+                Span::raw(" | "),
+            ]
+        })
+        .collect()
+}
+
+/// Adds the score of a finished game to the high scores and returns its rank.
+/// Draws a snake given from its tail to its head.
+pub(crate) fn draw_snake<'a>(
+    grid: &mut Grid,
+    snake: impl DoubleEndedIterator<Item = &'a Point>,
+    head_color: Color,
+    body_color: Color,
+) {
+    for (idx, p) in snake.rev().enumerate() {
+        let color = if idx == 0 { head_color } else { body_color };
+        grid[p.y as usize][p.x as usize] = Span::styled(SNAKE_CHAR, Style::default().fg(color));
+    }
+}
+
+/// Draws everything on the board of the game.
+pub(crate) fn game_grid(game: &Game) -> Grid {
+    let mut grid = new_grid(game.board);
+
+    draw_level(&mut grid, &game.walls, &game.portals);
+    draw_apple(&mut grid, &game.apple);
+    draw_items(&mut grid, &game.items);
+    for snake in game
+        .snakes
+        .iter()
+        .filter(|snake| snake.alive || snake.id == 0)
+    {
+        let (head_color, body_color) = snake_colors(snake.id);
+        draw_snake(&mut grid, snake.body.iter(), head_color, body_color);
+    }
+    draw_hazards(&mut grid, game.hazards.iter().map(|h| &h.position));
+    if let Some(cell) = game.cause().and_then(Cause::cell) {
+        draw_crash(&mut grid, cell);
+    }
+
+    grid
+}
+
+/// Draws the grid in the middle of the frame with the status lines below it.
+/// Returns false if there isn't enough space for the board.
+pub(crate) fn draw_board<'a, B: Backend>(
+    f: &mut Frame<B>,
+    grid: Grid,
+    border_color: Color,
+    status: impl Into<Text<'a>>,
+) -> bool {
+    let status = status.into();
+    let status_height = status.height() as u16;
+    let board_width = grid.first().map_or(0, |row| row.len()) as u16 * 2 + 2;
+    let board_height = grid.len() as u16 + 2;
+    let mut x = 0;
+    let mut y = 0;
+    let mut enough_space = true;
+
+    if let Some(res) = f.size().width.checked_sub(board_width) {
+        x = res / 2;
+    } else {
+        enough_space = false;
+    }
+
+    if let Some(res) = f.size().height.checked_sub(board_height + status_height) {
+        y = res / 2;
+    } else {
+        enough_space = false;
+    }
+
+    if !enough_space {
+        let text = Paragraph::new("Not enough screen space");
+        f.render_widget(text, f.size());
+        return false;
+    }
+
+    let chunks = [
+        Rect {
+            x,
+            y,
+            width: board_width,
+            height: board_height,
+        },
+        Rect {
+            x,
+            y: y + board_height,
+            width: (status.width() as u16).min(f.size().width - x),
+            height: status_height,
+        },
+    ];
+    let mut grid_text = Text::default();
+    for line in grid {
+        grid_text.extend(Text::from(Spans::from(line)));
+    }
+
+    let text = Paragraph::new(grid_text).block(
+        Block::default()
+            .border_style(Style::default().fg(border_color))
+            .borders(Borders::ALL)
+            .title("Snake"),
+    );
+    f.render_widget(text, chunks[0]);
+    f.render_widget(Paragraph::new(status), chunks[1]);
+
+    true
+}
+
+/// Draws the lines in a box in the middle of the frame, sized to fit them.
+pub(crate) fn draw_dialog<'a, B: Backend>(
+    f: &mut Frame<B>,
+    title: &str,
+    lines: Vec<Spans<'a>>,
+    alignment: Alignment,
+) {
+    let width = (lines.iter().map(Spans::width).max().unwrap_or(0) as u16 + 4).min(f.size().width);
+    let height = (lines.len() as u16 + 2).min(f.size().height);
+    let area = Rect {
+        x: (f.size().width - width) / 2,
+        y: (f.size().height - height) / 2,
+        width,
+        height,
+    };
+
+    let dialog = Paragraph::new(lines).alignment(alignment).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan))
+            .title(title.to_string()),
+    );
+    f.render_widget(dialog, area);
+}
+
+/// Draws the lifetime statistics in a box in the middle of the frame.
+pub(crate) fn draw_stats<B: Backend>(f: &mut Frame<B>, lines: &[String]) {
+    let mut text: Vec<Spans> = lines
+        .iter()
+        .map(|line| Spans::from(line.as_str()))
+        .collect();
+    text.push(Spans::from(""));
+    text.push(Spans::from("Press any key to go back"));
+
+    draw_dialog(f, "Stats", text, Alignment::Left);
+}
+
+/// Draws the screen shown once the snake fills the board, with the summary of
+/// the game and the keys that can be pressed.
+fn draw_won<B: Backend>(
+    f: &mut Frame<B>,
+    game: &Game,
+    speed: f64,
+    summary: Option<&[Span<'static>]>,
+    keys: Vec<Span>,
+) {
+    let score = match summary {
+        Some(summary) if !summary.is_empty() => Spans::from(summary.to_vec()),
+        _ => Spans::from(format!("Score {}", game.score())),
+    };
+
+    let lines = vec![
+        Spans::from(""),
+        Spans::from(Span::styled(
+            "The snake filled the board!",
+            Style::default().fg(Color::Yellow),
+        )),
+        Spans::from(""),
+        score,
+        Spans::from(format!("Length {}", game.snake().len())),
+        Spans::from(format!(
+            "Filled in {} ({} ticks)",
+            stats::format_duration(game.ticks as f64 / speed),
+            game.ticks
+        )),
+        Spans::from(""),
+        Spans::from(keys),
+    ];
+
+    draw_dialog(f, "You won", lines, Alignment::Center);
+}
 
+/// Records the score of a game that ended and returns the summary shown below
+/// the final board. `recorded` keeps the high score added for the run, which
+/// is replaced if `replace` is set, for a run rewound after it ended.
+pub(crate) fn finish_game(
+    high_scores: &mut HighScores,
+    recorded: &mut Option<HighScore>,
+    game: &Game,
+    options: &game::Options,
+    replace: bool,
+) -> Vec<Span<'static>> {
+    // games played by the solver or a bot don't make it to the high scores
+    if options.self_play || options.bot.is_some() {
+        return Vec::new();
+    }
+
+    let previous = recorded.take().filter(|_| replace);
+    let entry = HighScore::new(game);
+    let rank = match &previous {
+        Some(previous) => high_scores.replace(game.mode, previous, entry.clone()),
+        None => high_scores.insert(game.mode, entry.clone()),
+    };
+
+    if rank.is_some() || previous.is_some() {
+        // losing a high score isn't worth interrupting the game for
+        let _ = high_scores.save();
+    }
+    if rank.is_some() {
+        *recorded = Some(entry);
+    }
+
+    let note = match rank {
+        Some(rank) => Span::styled(
+            format!("(new high score #{}). ", rank + 1),
+            Style::default().fg(Color::Yellow),
+        ),
+        None => {
+            let best = high_scores.table(game.mode).first();
+            Span::raw(format!("(best {}). ", best.map_or(0, |best| best.score)))
+        }
+    };
+
+    vec![Span::raw(format!("Score {} ", game.score())), note]
+}
+
+/// Saves a game that was left before it was over and returns whether it was
+/// saved. Zen games are over once they are left.
+pub(crate) fn save_unfinished(
+    options: &game::Options,
+    mut game: Game,
+) -> Result<bool, Box<dyn Error>> {
+    if game.is_finished() || game.mode == Mode::Zen {
+        return Ok(false);
+    }
+
+    if game.is_running() {
+        game.toggle_pause();
+    }
+    SavedGame::save(options, game)?;
+
+    Ok(true)
+}
+
+/// Plays games with the options, starting from `game` if one is given. A game
+/// left before it is over is saved so that it can be resumed later.
+pub fn run_ui(options: game::Options, game: Option<Game>) -> Result<(), Box<dyn Error>> {
     let events = EventHandle::new(options.speed);
+    let mut high_scores = HighScores::load();
 
-    let mut game: Game = Game::new(&options);
-    let game_area = Solver::new(&game, None).game_area;
+    let game = game.unwrap_or_else(|| Game::new(&options));
+    let mut recorded = None;
+    let on_finish = |game: &Game, replace: bool| {
+        finish_game(&mut high_scores, &mut recorded, game, &options, replace)
+    };
 
-    let mut dir = game.dir.clone();
-    let apple_char = "🍎";
-    let snake_char = "██";
+    let result = match &options.record_cast {
+        Some(path) => {
+            let (width, height) = crossterm::terminal::size()?;
+            let out = CastWriter::new(io::stdout(), Cast::create(path, width, height)?);
+            play(
+                &mut setup_terminal_with(out)?,
+                &events,
+                &options,
+                game,
+                on_finish,
+            )
+        }
+        None => play(&mut setup_terminal()?, &events, &options, game, on_finish),
+    };
 
-    loop {
-        let mut grid = vec![vec![Span::raw("  "); game.board.0 as usize]; game.board.1 as usize];
+    restore_terminal()?;
+    if save_unfinished(&options, result?)? {
+        println!("The game is saved, continue it with --resume");
+    }
 
-        grid[game.apple.y as usize][game.apple.x as usize] =
-            Span::styled(apple_char, Style::default().fg(Color::Red));
+    Ok(())
+}
 
-        grid[game.snake.back().unwrap().y as usize][game.snake.back().unwrap().x as usize] =
-            Span::styled(snake_char, Style::default().fg(Color::Blue));
+/// Plays `game` and then new games with the options until the player quits,
+/// and returns the game the player quit. Every game that ends, or zen game
+/// once it is left, is added to the history and `on_finish` is called with
+/// it, returning a summary shown below the final board. `on_finish` is told to
+/// replace what it recorded for a game rewound after it ended.
+pub(crate) fn play<B: Backend>(
+    terminal: &mut Terminal<B>,
+    events: &EventHandle,
+    options: &game::Options,
+    mut game: Game,
+    mut on_finish: impl FnMut(&Game, bool) -> Vec<Span<'static>>,
+) -> Result<Game, Box<dyn Error>> {
+    events.set_speed(options.speed);
 
-        for p in game.snake.iter().rev().skip(1) {
-            grid[p.y as usize][p.x as usize] =
-                Span::styled(snake_char, Style::default().fg(Color::Yellow));
+    let mut solver = Solver::new(&game);
+    let mut rivals = new_rivals(&game, options);
+    let mut bot = match &options.bot {
+        Some(command) => Some(Bot::spawn(
+            command,
+            Duration::from_millis(options.bot_timeout),
+        )?),
+        None => None,
+    };
+    let mut broadcaster = match &options.broadcast {
+        Some(addr) => Some(Broadcaster::bind(addr)?),
+        None => None,
+    };
+
+    // games are rewound a second at a time
+    let rewind_step = options.speed.round().max(1.0) as usize;
+    let mut history = (options.rewind > 0 && !options.mode.is_ranked())
+        .then(|| History::new(options.rewind as usize * rewind_step));
+
+    let new_replay = |game: &Game| {
+        options
+            .record
+            .as_ref()
+            .map(|_| Replay::new(game, options.speed))
+    };
+    let save_replay = |replay: &Option<Replay>| match (&options.record, replay) {
+        (Some(path), Some(replay)) => replay.save(path),
+        _ => Ok(()),
+    };
+    let mut replay = new_replay(&game);
+
+    let mut dir = game.dir().clone();
+    let mut summary = None;
+    // set once the run is in the history, so that a run rewound after it ended
+    // replaces its record rather than adding another
+    let mut recorded = false;
+    // lines of the stats screen while it is shown
+    let mut stats_screen: Option<Vec<String>> = None;
+
+    loop {
+        if game.is_finished() && summary.is_none() {
+            stats::record_game(&game, options, recorded);
+            summary = Some(on_finish(&game, recorded));
+            recorded = true;
+            save_replay(&replay)?;
+        }
+
+        let grid = game_grid(&game);
+
+        if let Some(broadcaster) = &mut broadcaster {
+            broadcaster.publish(&game);
         }
 
         terminal.draw(|f| {
-            let board_width = game.board.0 * 2 + 2;
-            let board_height = game.board.1 + 2;
-            let mut x = 0;
-            let mut y = 0;
-            let mut enough_space = true;
-
-            if let Some(res) = f.size().width.checked_sub(board_width as u16) {
-                x = res / 2;
-            } else {
-                enough_space = false;
+            if let Some(lines) = &stats_screen {
+                draw_stats(f, lines);
+                return;
             }
 
-            if let Some(res) = f.size().height.checked_sub(board_height as u16 + 1) {
-                y = res / 2;
-            } else {
-                enough_space = false;
+            let mut end_keys = vec![
+                Span::raw("press "),
+                Span::styled("q", Style::default().fg(Color::Red)),
+                Span::raw(" to quit, "),
+                Span::raw("press "),
+                Span::styled("r", Style::default().fg(Color::Yellow)),
+                Span::raw(" to replay"),
+                Span::raw(", "),
+                Span::styled("t", Style::default().fg(Color::Cyan)),
+                Span::raw(" for stats"),
+            ];
+            if history.as_ref().is_some_and(|history| !history.is_empty()) {
+                end_keys.extend([
+                    Span::raw(", "),
+                    Span::styled("u", Style::default().fg(Color::Cyan)),
+                    Span::raw(" to rewind"),
+                ]);
             }
 
-            if !enough_space {
-                if game.is_running() {
-                    game.toggle_pause();
-                }
-
-                let text = Paragraph::new("Not enough screen space");
-                f.render_widget(text, f.size());
+            if game.is_won() {
+                draw_won(f, &game, options.speed, summary.as_deref(), end_keys);
                 return;
             }
 
-            let key_help = if game.is_game_over() {
+            let key_help = if game.is_finished() {
+                let mut spans = vec![match game.cause() {
+                    Some(Cause::TimeUp) => Span::raw("Time's up. "),
+                    Some(cause) => Span::raw(format!("Game is over, {}. ", cause)),
+                    None => Span::raw("Level complete! "),
+                }];
+
+                spans.extend(summary.iter().flatten().cloned());
+                spans.extend(end_keys);
+                Spans::from(spans)
+            } else if !game.is_running() {
+                Spans::from(Span::raw("Paused"))
+            } else if let Some(warning) = bot.as_ref().and_then(|bot| bot.warning.as_ref()) {
                 Spans::from(vec![
-                    Span::raw("Game is over. press "),
-                    Span::styled("q", Style::default().fg(Color::Red)),
-                    Span::raw(" to quit, "),
-                    Span::raw("press "),
-                    Span::styled("r", Style::default().fg(Color::Yellow)),
-                    Span::raw(" to replay"),
+                    Span::styled("Bot: ", Style::default().fg(Color::Yellow)),
+                    Span::raw(warning.as_str()),
                 ])
+            } else {
+                let mut spans = mode_spans(&game, options.speed);
+                spans.extend(effect_spans(&game));
+                spans.push(Span::raw(
+                    "Controls: wasd hjkl ←↓↑→, Quit: q, Pause: p space, Stats: t",
+                ));
+                if history.is_some() {
+                    spans.push(Span::raw(", Rewind: u"));
+                }
+                Spans::from(spans)
+            };
+
+            let border_color = if game.is_game_over() {
+                Color::Red
+            } else if game.is_completed() {
+                Color::Cyan
             } else if !game.is_running() {
-                Spans::from(Span::raw("Paused"))
+                Color::Yellow
             } else {
-                Spans::from(Span::raw(
-                    "Controls: wasd hjkl ←↓↑→, Quit: q, Pause: p space",
-                ))
+                Color::Green
             };
 
-            let chunks = vec![
-                Rect {
-                    x,
-                    y,
-                    width: (game.board.0 * 2 + 2) as u16,
-                    height: (game.board.1 + 2) as u16,
-                },
-                Rect {
-                    x,
-                    y: y + (game.board.1 + 2) as u16,
-                    width: (key_help.width() as u16).min(f.size().width - x),
-                    height: 1,
-                },
-            ];
-            let mut grid_text = Text::default();
-            for line in grid {
-                grid_text.extend(Text::from(Spans::from(line)));
+            if !draw_board(f, grid, border_color, key_help) && game.is_running() {
+                game.toggle_pause();
             }
-
-            let text = Paragraph::new(grid_text).block(
-                Block::default()
-                    .border_style(Style::default().fg(if game.is_game_over() {
-                        Color::Red
-                    } else if !game.is_running() {
-                        Color::Yellow
-                    } else {
-                        Color::Green
-                    }))
-                    .borders(Borders::ALL)
-                    .title("Snake"),
-            );
-            f.render_widget(text, chunks[0]);
-            f.render_widget(Paragraph::new(key_help), chunks[1]);
         })?;
 
         match events.next()? {
+            Event::Input(_) if stats_screen.is_some() => stats_screen = None,
             Event::Input(key) => match key.code {
-                KeyCode::Char('q') => break,
+                KeyCode::Char('q') => {
+                    // zen games never end, so they count once they're left
+                    if game.mode == Mode::Zen && summary.is_none() {
+                        stats::record_game(&game, options, recorded);
+                        on_finish(&game, recorded);
+                    }
+                    save_replay(&replay)?;
+                    break;
+                }
                 KeyCode::Char('a') | KeyCode::Char('h') | KeyCode::Left => dir = Direction::Left,
                 KeyCode::Char('s') | KeyCode::Char('j') | KeyCode::Down => dir = Direction::Down,
                 KeyCode::Char('w') | KeyCode::Char('k') | KeyCode::Up => dir = Direction::Up,
                 KeyCode::Char('d') | KeyCode::Char('l') | KeyCode::Right => dir = Direction::Right,
                 KeyCode::Char('r') => {
-                    game = Game::new(&options);
-                    dir = game.dir.clone();
+                    if game.mode == Mode::Zen && summary.is_none() {
+                        stats::record_game(&game, options, recorded);
+                        on_finish(&game, recorded);
+                    }
+                    summary = None;
+                    recorded = false;
+                    game = Game::new(options);
+                    solver = Solver::new(&game);
+                    rivals = new_rivals(&game, options);
+                    dir = game.dir().clone();
+                    if let Some(history) = &mut history {
+                        history.clear();
+                    }
+                    replay = new_replay(&game);
                     continue;
                 }
+                KeyCode::Char('u') => {
+                    if let Some(previous) = history
+                        .as_mut()
+                        .and_then(|history| history.rewind(rewind_step))
+                    {
+                        summary = None;
+                        game = previous;
+                        game.assisted = true;
+                        if game.is_running() {
+                            game.toggle_pause();
+                        }
+                        solver = Solver::new(&game);
+                        rivals = new_rivals(&game, options);
+                        dir = game.dir().clone();
+                        if let Some(replay) = &mut replay {
+                            replay.truncate(&game);
+                        }
+                    }
+                }
                 KeyCode::Char('p') | KeyCode::Char(' ') => game.toggle_pause(),
+                KeyCode::Char('t') => {
+                    if game.is_running() {
+                        game.toggle_pause();
+                    }
+                    stats_screen = Some(stats::summary(&stats::History::load()));
+                }
                 _ => {}
             },
             Event::Tick if game.is_running() => {
+                if let Some(history) = &mut history {
+                    history.push(&game);
+                }
+
                 if game.self_play {
-                    let mut solver = Solver::new(&game, Some(game_area.clone()));
-                    dir = solver.next_direction();
+                    dir = solver.next_direction(&game);
+                } else if let Some(bot) = &mut bot {
+                    dir = bot.next_direction(&game);
                 }
 
-                game.move_snake(dir.clone());
+                let mut dirs = vec![dir.clone()];
+                dirs.extend(rivals.iter_mut().map(|rival| rival.next_direction(&game)));
+                if let Some(replay) = &mut replay {
+                    replay.push(&dirs);
+                }
+                game.step(&dirs);
             }
             _ => {}
         }
     }
 
-    disable_raw_mode()?;
-    execute!(io::stdout(), LeaveAlternateScreen)?;
-
-    Ok(())
+    Ok(game)
 }
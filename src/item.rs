@@ -0,0 +1,68 @@
+use crate::game::Point;
+use serde::{Deserialize, Serialize};
+
+/// Chance of an item appearing on a tick while there is room for one.
+pub const SPAWN_CHANCE: f64 = 0.02;
+/// Most items on the board at once.
+pub const MAX_ITEMS: usize = 2;
+/// Ticks an item stays on the board before disappearing.
+pub const LIFETIME: u64 = 60;
+/// Points given by a golden apple.
+pub const GOLDEN_APPLE_POINTS: usize = 5;
+/// Segments cut from the tail by a shrink pill.
+pub const SHRINK_LENGTH: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemKind {
+    GoldenApple,
+    ShrinkPill,
+    SlowMotion,
+    Ghost,
+}
+
+impl ItemKind {
+    pub const ALL: [ItemKind; 4] = [
+        ItemKind::GoldenApple,
+        ItemKind::ShrinkPill,
+        ItemKind::SlowMotion,
+        ItemKind::Ghost,
+    ];
+
+    /// Name of the item in level files.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ItemKind::GoldenApple => "golden_apple",
+            ItemKind::ShrinkPill => "shrink_pill",
+            ItemKind::SlowMotion => "slow_motion",
+            ItemKind::Ghost => "ghost",
+        }
+    }
+
+    /// Ticks the effect of the item lasts, if it has a lasting effect.
+    pub fn duration(&self) -> Option<u64> {
+        match self {
+            ItemKind::SlowMotion => Some(40),
+            ItemKind::Ghost => Some(30),
+            ItemKind::GoldenApple | ItemKind::ShrinkPill => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Item {
+    pub kind: ItemKind,
+    pub position: Point,
+    /// Tick after which the item disappears. Items placed by a level never
+    /// do.
+    pub expires: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Effect {
+    pub kind: ItemKind,
+    /// Snake that picked up the item.
+    pub snake: usize,
+    /// Last tick the effect is active on.
+    pub until: u64,
+}
@@ -0,0 +1,394 @@
+use crate::game::{Direction, Point};
+use crate::hazard::{Hazard, Patrol};
+use crate::item::ItemKind;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+
+/// A board layout read from a level file.
+///
+/// A level file starts with optional `key = value` lines followed by a `[map]`
+/// line and the rows of the board:
+///
+/// ```text
+/// name = Corridors
+/// length = 4
+/// [map]
+/// ##########
+/// #........#
+/// #>...a...#
+/// #........#
+/// #...a....#
+/// #........#
+/// ##########
+/// ```
+///
+/// `#` is a wall, `.` an empty cell and one of `^`, `v`, `<` and `>` the
+/// starting head of the snake facing that way. Every lowercase letter marks
+/// one end of a portal and must appear exactly twice, away from walls and
+/// other portals.
+///
+/// Hazards are added with the keys below, which may be repeated:
+///
+/// - `bounce = 3,4 right` moves back and forth starting at (3, 4)
+/// - `circle = 3,4 3` circles around a 3x3 square with (3, 4) as its top left
+///   corner
+/// - `path = 1,1 6,1 6,3` walks the lines between the points and back, or
+///   around if the last point is the first one
+///
+/// Items that stay on the board until they are picked up are placed with
+/// `item = 3,4 ghost`, one of `golden_apple`, `shrink_pill`, `slow_motion` and
+/// `ghost`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Level {
+    pub name: String,
+    pub width: i32,
+    pub height: i32,
+    pub walls: Vec<Point>,
+    pub portals: Vec<(Point, Point)>,
+    pub hazards: Vec<Hazard>,
+    pub items: Vec<(Point, ItemKind)>,
+    pub start: Option<(Point, Direction)>,
+    pub length: Option<i32>,
+    pub speed: Option<f64>,
+    /// Apples to eat to complete the level.
+    pub goal: Option<usize>,
+}
+
+impl Level {
+    pub fn load(path: &str) -> Result<Level, Box<dyn Error>> {
+        let text = fs::read_to_string(path)?;
+        Level::parse(&text).map_err(|err| format!("{}: {}", path, err).into())
+    }
+
+    pub fn parse(text: &str) -> Result<Level, String> {
+        let mut level = Level::default();
+        let mut lines = text.lines().enumerate();
+
+        for (idx, line) in lines.by_ref() {
+            let line = line.trim();
+
+            if line == "[map]" {
+                break;
+            } else if line.is_empty() {
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected `key = value`", idx + 1))?;
+            let (key, value) = (key.trim(), value.trim());
+            let invalid = || format!("line {}: invalid value for {}", idx + 1, key);
+
+            match key {
+                "name" => level.name = value.to_string(),
+                "length" => level.length = Some(value.parse().map_err(|_| invalid())?),
+                "speed" => {
+                    let speed: f64 = value.parse().map_err(|_| invalid())?;
+                    if !(speed > 0.0 && speed.is_finite()) {
+                        return Err(invalid());
+                    }
+                    level.speed = Some(speed);
+                }
+                "goal" => level.goal = Some(value.parse().map_err(|_| invalid())?),
+                "bounce" | "circle" | "path" => {
+                    let hazard = parse_hazard(key, value)
+                        .map_err(|err| format!("line {}: {}", idx + 1, err))?;
+                    level.hazards.push(hazard);
+                }
+                "item" => {
+                    let item =
+                        parse_item(value).map_err(|err| format!("line {}: {}", idx + 1, err))?;
+                    level.items.push(item);
+                }
+                _ => return Err(format!("line {}: unknown key {}", idx + 1, key)),
+            }
+        }
+
+        let mut portal_ends: Vec<(char, Point)> = Vec::new();
+
+        for (y, (idx, line)) in lines
+            .filter(|(_, line)| !line.trim().is_empty())
+            .enumerate()
+        {
+            let row: Vec<char> = line.trim().chars().collect();
+
+            if y == 0 {
+                level.width = row.len() as i32;
+            } else if row.len() as i32 != level.width {
+                return Err(format!("line {}: rows must have the same width", idx + 1));
+            }
+
+            for (x, c) in row.into_iter().enumerate() {
+                let point = Point::new(x as i32, y as i32);
+                let direction = match c {
+                    '^' => Some(Direction::Up),
+                    'v' => Some(Direction::Down),
+                    '<' => Some(Direction::Left),
+                    '>' => Some(Direction::Right),
+                    _ => None,
+                };
+
+                match c {
+                    '.' => (),
+                    '#' => level.walls.push(point),
+                    'a'..='z' => portal_ends.push((c, point)),
+                    _ if direction.is_some() => {
+                        if level.start.is_some() {
+                            return Err("the level has more than one start".to_string());
+                        }
+                        level.start = Some((point, direction.unwrap()));
+                    }
+                    _ => return Err(format!("line {}: unknown tile {:?}", idx + 1, c)),
+                }
+            }
+
+            level.height += 1;
+        }
+
+        if level.width == 0 || level.height == 0 {
+            return Err("the level has no map".to_string());
+        }
+
+        let inside = |p: &Point| p.x >= 0 && p.x < level.width && p.y >= 0 && p.y < level.height;
+        if !level
+            .hazards
+            .iter()
+            .flat_map(Hazard::cells)
+            .all(|p| inside(&p))
+        {
+            return Err("a hazard leaves the map".to_string());
+        }
+
+        if !level.items.iter().all(|(p, _)| inside(p)) {
+            return Err("an item is outside the map".to_string());
+        }
+
+        portal_ends.sort_by_key(|(c, _)| *c);
+        for ends in portal_ends.chunk_by(|a, b| a.0 == b.0) {
+            match ends {
+                [(_, a), (_, b)] => level.portals.push((a.clone(), b.clone())),
+                _ => return Err(format!("portal {} must appear exactly twice", ends[0].0)),
+            }
+        }
+
+        if level.has_blocked_portal() {
+            return Err("a portal opens onto a wall or another portal".to_string());
+        }
+
+        let on_tile = |p: &Point| {
+            level.walls.contains(p) || level.portals.iter().any(|(a, b)| a == p || b == p)
+        };
+        if level
+            .hazards
+            .iter()
+            .flat_map(Hazard::cells)
+            .any(|p| on_tile(&p))
+        {
+            return Err("a hazard crosses a wall or a portal".to_string());
+        }
+
+        if level.items.iter().any(|(p, _)| on_tile(p)) {
+            return Err("an item is on a wall or a portal".to_string());
+        }
+
+        Ok(level)
+    }
+
+    /// Returns true if a portal is next to a wall or to another portal, which
+    /// a snake coming out of it could run into.
+    pub fn has_blocked_portal(&self) -> bool {
+        let ends: Vec<&Point> = self.portals.iter().flat_map(|(a, b)| [a, b]).collect();
+
+        ends.iter().any(|end| {
+            self.walls
+                .iter()
+                .chain(ends.iter().copied())
+                .any(|tile| tile.manhattan_distance(end) == 1)
+        })
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.name.is_empty() {
+            writeln!(f, "name = {}", self.name)?;
+        }
+        if let Some(length) = self.length {
+            writeln!(f, "length = {}", length)?;
+        }
+        if let Some(speed) = self.speed {
+            writeln!(f, "speed = {}", speed)?;
+        }
+        if let Some(goal) = self.goal {
+            writeln!(f, "goal = {}", goal)?;
+        }
+        for hazard in &self.hazards {
+            let position = format_point(&hazard.position);
+
+            match &hazard.patrol {
+                Patrol::Bounce { direction } => {
+                    writeln!(f, "bounce = {} {}", position, direction_name(direction))?
+                }
+                Patrol::Route { .. } => {
+                    let waypoints: Vec<String> =
+                        hazard.waypoints().iter().map(format_point).collect();
+                    writeln!(f, "path = {}", waypoints.join(" "))?
+                }
+            }
+        }
+        for (position, kind) in &self.items {
+            writeln!(f, "item = {} {}", format_point(position), kind.name())?;
+        }
+        writeln!(f, "[map]")?;
+
+        let mut rows = vec![vec!['.'; self.width as usize]; self.height as usize];
+
+        for wall in &self.walls {
+            rows[wall.y as usize][wall.x as usize] = '#';
+        }
+
+        for (idx, (a, b)) in self.portals.iter().enumerate() {
+            let c = (b'a' + (idx % 26) as u8) as char;
+            rows[a.y as usize][a.x as usize] = c;
+            rows[b.y as usize][b.x as usize] = c;
+        }
+
+        if let Some((head, direction)) = &self.start {
+            rows[head.y as usize][head.x as usize] = match direction {
+                Direction::Up => '^',
+                Direction::Down => 'v',
+                Direction::Left => '<',
+                Direction::Right => '>',
+            };
+        }
+
+        for row in rows {
+            writeln!(f, "{}", row.into_iter().collect::<String>())?;
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_point(text: &str) -> Result<Point, String> {
+    let invalid = || format!("invalid point {:?}", text);
+    let (x, y) = text.split_once(',').ok_or_else(invalid)?;
+
+    Ok(Point::new(
+        x.trim().parse().map_err(|_| invalid())?,
+        y.trim().parse().map_err(|_| invalid())?,
+    ))
+}
+
+fn format_point(point: &Point) -> String {
+    format!("{},{}", point.x, point.y)
+}
+
+fn direction_name(direction: &Direction) -> &'static str {
+    match direction {
+        Direction::Up => "up",
+        Direction::Down => "down",
+        Direction::Left => "left",
+        Direction::Right => "right",
+    }
+}
+
+fn parse_hazard(key: &str, value: &str) -> Result<Hazard, String> {
+    let words: Vec<&str> = value.split_whitespace().collect();
+
+    match (key, words.as_slice()) {
+        ("bounce", [point, direction]) => {
+            let direction = match *direction {
+                "up" => Direction::Up,
+                "down" => Direction::Down,
+                "left" => Direction::Left,
+                "right" => Direction::Right,
+                _ => return Err(format!("invalid direction {:?}", direction)),
+            };
+
+            Ok(Hazard::bounce(parse_point(point)?, direction))
+        }
+        ("circle", [corner, size]) => {
+            let size = size
+                .parse()
+                .map_err(|_| format!("invalid size {:?}", size))?;
+
+            Ok(Hazard::circle(parse_point(corner)?, size))
+        }
+        ("path", points) if !points.is_empty() => {
+            let waypoints = points
+                .iter()
+                .map(|point| parse_point(point))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Hazard::path(&waypoints)
+        }
+        _ => Err(format!("invalid value for {}", key)),
+    }
+}
+
+fn parse_item(value: &str) -> Result<(Point, ItemKind), String> {
+    let (point, name) = value
+        .split_once(char::is_whitespace)
+        .ok_or("invalid value for item")?;
+    let name = name.trim();
+    let kind = ItemKind::ALL
+        .into_iter()
+        .find(|kind| kind.name() == name)
+        .ok_or_else(|| format!("unknown item {:?}", name))?;
+
+    Ok((parse_point(point)?, kind))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEVEL: &str = "\
+name = Test
+length = 4
+speed = 12.5
+goal = 10
+bounce = 1,1 right
+circle = 5,4 3
+path = 1,6 4,6
+item = 8,1 ghost
+[map]
+##########
+#........#
+#.a......#
+#......#.#
+#>.......#
+#.....a..#
+#........#
+##########
+";
+
+    #[test]
+    fn level_survives_a_round_trip() {
+        let level = Level::parse(LEVEL).unwrap();
+        let text = level.to_string();
+
+        assert_eq!(level.portals.len(), 1);
+        assert_eq!(level.hazards.len(), 3);
+        assert_eq!(Level::parse(&text), Ok(level));
+    }
+
+    #[test]
+    fn portal_next_to_a_wall_is_rejected() {
+        let text = LEVEL.replace("#.a......#", "#a.......#");
+
+        assert!(Level::parse(&text).is_err());
+    }
+
+    #[test]
+    fn speed_must_be_positive() {
+        for speed in ["0", "-2", "inf"] {
+            let text = LEVEL.replace("speed = 12.5", &format!("speed = {}", speed));
+
+            assert!(Level::parse(&text).is_err(), "speed {}", speed);
+        }
+    }
+}
@@ -0,0 +1,26 @@
+pub mod bot;
+pub mod broadcast;
+pub mod campaign;
+pub mod cast;
+pub mod client;
+pub mod editor;
+pub mod env;
+mod event;
+pub mod game;
+pub mod hazard;
+pub mod highscore;
+pub mod interface;
+pub mod item;
+pub mod level;
+pub mod menu;
+pub mod mode;
+pub mod net;
+pub mod render;
+pub mod replay;
+pub mod rewind;
+pub mod rival;
+pub mod save;
+pub mod server;
+pub mod solver;
+pub mod stats;
+pub mod tournament;
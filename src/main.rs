@@ -1,10 +1,11 @@
-mod event;
-mod game;
-mod interface;
-mod solver;
-
+use clap::parser::ValueSource;
 use clap::{Arg, ArgAction, Command};
-use interface::run_ui;
+use snake::interface::run_ui;
+use snake::level::Level;
+use snake::render::{self, Theme};
+use snake::save::SavedGame;
+use snake::tournament;
+use snake::{broadcast, campaign, cast, client, editor, game, menu, replay, server, stats};
 use std::error::Error;
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -92,7 +93,338 @@ fn main() -> Result<(), Box<dyn Error>> {
                 .value_name("ALG")
                 .value_parser(["astar", "bfs"]),
         )
+        .arg(
+            Arg::new("seed")
+                .long("seed")
+                .help("Seed used for placing the apples")
+                .value_name("SEED")
+                .value_parser(clap::value_parser!(u64)),
+        )
+        .arg(
+            Arg::new("bot")
+                .long("bot")
+                .help("Command of an external program that controls the snake")
+                .conflicts_with("self_play")
+                .value_name("COMMAND"),
+        )
+        .arg(
+            Arg::new("bot_timeout")
+                .long("bot-timeout")
+                .help("Milliseconds to wait for the bot's reply each tick")
+                .requires("bot")
+                .default_value("100")
+                .value_name("MS")
+                .value_parser(clap::value_parser!(u64)),
+        )
+        .arg(
+            Arg::new("rivals")
+                .long("rivals")
+                .help("Number of computer controlled snakes competing for the apples")
+                .conflicts_with("self_play")
+                .default_value("0")
+                .value_name("COUNT")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("rival_level")
+                .long("rival-level")
+                .help("How careful the rivals are")
+                .default_value("normal")
+                .value_name("LEVEL")
+                .value_parser(["easy", "normal", "hard"]),
+        )
+        .arg(
+            Arg::new("items")
+                .long("items")
+                .help("Spawn power-ups now and then")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("level")
+                .long("level")
+                .help("Play on the board described in a level file")
+                .value_name("FILE"),
+        )
+        .arg(
+            Arg::new("portals")
+                .long("portals")
+                .help("Number of portal pairs placed at random")
+                .default_value("0")
+                .value_name("N")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("hazards")
+                .long("hazards")
+                .help("Number of hazards patrolling the board")
+                .default_value("0")
+                .value_name("N")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("mode")
+                .long("mode")
+                .help("Game mode")
+                .default_value("endless")
+                .value_name("MODE")
+                .value_parser(["endless", "time-attack", "survival", "zen"]),
+        )
+        .arg(
+            Arg::new("time_limit")
+                .long("time-limit")
+                .help("Length of a time attack game in seconds")
+                .default_value("60")
+                .value_name("SECONDS")
+                .value_parser(clap::value_parser!(u64).range(1..)),
+        )
+        .arg(
+            Arg::new("rewind")
+                .long("rewind")
+                .help("Seconds of play that can be rewound, not available in time attack and survival")
+                .default_value("0")
+                .value_name("SECONDS")
+                .value_parser(clap::value_parser!(u64)),
+        )
+        .arg(
+            Arg::new("record")
+                .long("record")
+                .help("Save a replay of the last game played, to be watched with the replay command")
+                .value_name("FILE"),
+        )
+        .arg(
+            Arg::new("record_cast")
+                .long("record-cast")
+                .help("Record what is drawn on the terminal as an asciicast v2 file")
+                .value_name("FILE"),
+        )
+        .arg(
+            Arg::new("resume")
+                .long("resume")
+                .help("Continue the game saved when quitting, with the options it was started with")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("broadcast")
+                .long("broadcast")
+                .help("Publish the game to spectators connecting to this address")
+                .value_name("HOST:PORT"),
+        )
+        .subcommand(
+            Command::new("tournament")
+                .about("Run bots over a set of seeds and boards and rank them")
+                .arg(
+                    Arg::new("config")
+                        .help("TOML file describing the bots, boards and seeds")
+                        .required(true)
+                        .value_name("FILE"),
+                )
+                .arg(
+                    Arg::new("format")
+                        .short('f')
+                        .long("format")
+                        .help("Output format of the leaderboard")
+                        .default_value("table")
+                        .value_name("FORMAT")
+                        .value_parser(["table", "json"]),
+                ),
+        )
+        .subcommand(Command::new("campaign").about("Play the built-in levels one after another"))
+        .subcommand(
+            Command::new("edit")
+                .about("Edit a level file, or create one with the width and height given before the command")
+                .arg(
+                    Arg::new("file")
+                        .help("Level file to edit")
+                        .value_name("FILE"),
+                ),
+        )
+        .subcommand(
+            Command::new("replay")
+                .about("Watch a game saved with --record")
+                .arg(
+                    Arg::new("file")
+                        .help("Replay file")
+                        .required(true)
+                        .value_name("FILE"),
+                ),
+        )
+        .subcommand(
+            Command::new("cast")
+                .about("Convert a replay to an asciicast v2 file without a terminal")
+                .arg(
+                    Arg::new("replay")
+                        .help("Replay file saved with --record")
+                        .required(true)
+                        .value_name("REPLAY"),
+                )
+                .arg(
+                    Arg::new("output")
+                        .help("Cast file to write")
+                        .required(true)
+                        .value_name("OUTPUT"),
+                ),
+        )
+        .subcommand(
+            Command::new("render")
+                .about("Draw a replay as an animated GIF or as PNG images without a terminal")
+                .arg(
+                    Arg::new("replay")
+                        .help("Replay file saved with --record")
+                        .required(true)
+                        .value_name("REPLAY"),
+                )
+                .arg(
+                    Arg::new("output")
+                        .help("GIF file, or directory for the PNG images")
+                        .required(true)
+                        .value_name("OUTPUT"),
+                )
+                .arg(
+                    Arg::new("format")
+                        .short('f')
+                        .long("format")
+                        .help("Image format")
+                        .default_value("gif")
+                        .value_name("FORMAT")
+                        .value_parser(["gif", "png"]),
+                )
+                .arg(
+                    Arg::new("cell_size")
+                        .long("cell-size")
+                        .help("Size of a cell in pixels")
+                        .default_value("16")
+                        .value_name("PIXELS")
+                        .value_parser(clap::value_parser!(usize)),
+                )
+                .arg(
+                    Arg::new("theme")
+                        .long("theme")
+                        .help("Colors to use, dark, light or a TOML file")
+                        .default_value("dark")
+                        .value_name("THEME"),
+                ),
+        )
+        .subcommand(
+            Command::new("stats")
+                .about("Show statistics of the games played")
+                .arg(
+                    Arg::new("export")
+                        .long("export")
+                        .help("Print every game played instead, as CSV or JSON")
+                        .value_name("FORMAT")
+                        .value_parser(["csv", "json"]),
+                ),
+        )
+        .subcommand(
+            Command::new("serve")
+                .about("Host a multiplayer game using the game options given before the command")
+                .arg(
+                    Arg::new("port")
+                        .long("port")
+                        .help("Port to listen on")
+                        .default_value("7777")
+                        .value_name("PORT")
+                        .value_parser(clap::value_parser!(u16)),
+                )
+                .arg(
+                    Arg::new("players")
+                        .long("players")
+                        .help("Number of ready players needed to start a round")
+                        .default_value("2")
+                        .value_name("COUNT")
+                        .value_parser(clap::value_parser!(usize)),
+                ),
+        )
+        .subcommand(
+            Command::new("join")
+                .about("Join a multiplayer game")
+                .arg(
+                    Arg::new("address")
+                        .help("Address of the server")
+                        .required(true)
+                        .value_name("HOST:PORT"),
+                )
+                .arg(
+                    Arg::new("name")
+                        .short('n')
+                        .long("name")
+                        .help("Name shown to the other players")
+                        .default_value("player")
+                        .value_name("NAME"),
+                ),
+        )
+        .subcommand(
+            Command::new("watch")
+                .about("Watch a game published with --broadcast")
+                .arg(
+                    Arg::new("address")
+                        .help("Address of the broadcasting game")
+                        .required(true)
+                        .value_name("HOST:PORT"),
+                ),
+        )
         .get_matches();
 
-    run_ui(game::Options::from(matches))
+    match matches.subcommand() {
+        Some(("tournament", matches)) => tournament::run(
+            matches.get_one::<String>("config").unwrap(),
+            tournament::Format::from(matches.get_one::<String>("format").unwrap()),
+        ),
+        Some(("campaign", _)) => campaign::run(),
+        Some(("edit", edit_matches)) => editor::run(
+            edit_matches.get_one::<String>("file"),
+            *matches.get_one::<i32>("width").unwrap(),
+            *matches.get_one::<i32>("height").unwrap(),
+        ),
+        Some(("replay", matches)) => replay::view(matches.get_one::<String>("file").unwrap()),
+        Some(("cast", matches)) => cast::convert(
+            matches.get_one::<String>("replay").unwrap(),
+            matches.get_one::<String>("output").unwrap(),
+        ),
+        Some(("render", matches)) => render::render(
+            matches.get_one::<String>("replay").unwrap(),
+            matches.get_one::<String>("output").unwrap(),
+            render::Format::from(matches.get_one::<String>("format").unwrap()),
+            *matches.get_one::<usize>("cell_size").unwrap(),
+            &Theme::load(matches.get_one::<String>("theme").unwrap())?,
+        ),
+        Some(("stats", matches)) => {
+            stats::run(matches.get_one::<String>("export").map(stats::Export::from))
+        }
+        Some(("serve", serve_matches)) => server::run(
+            game_options(&matches)?,
+            *serve_matches.get_one::<u16>("port").unwrap(),
+            *serve_matches.get_one::<usize>("players").unwrap(),
+        ),
+        Some(("join", matches)) => client::run(
+            matches.get_one::<String>("address").unwrap(),
+            matches.get_one::<String>("name").unwrap(),
+        ),
+        Some(("watch", matches)) => broadcast::watch(matches.get_one::<String>("address").unwrap()),
+        _ if matches.get_flag("resume") => {
+            let (options, game) = SavedGame::resume()?;
+            run_ui(options, Some(game))
+        }
+        _ if has_game_flags(&matches) => run_ui(game_options(&matches)?, None),
+        _ => menu::run(),
+    }
+}
+
+/// Whether any of the game options were given on the command line, in which
+/// case the start menu is skipped.
+fn has_game_flags(matches: &clap::ArgMatches) -> bool {
+    matches
+        .ids()
+        .any(|id| matches.value_source(id.as_str()) == Some(ValueSource::CommandLine))
+}
+
+fn game_options(matches: &clap::ArgMatches) -> Result<game::Options, Box<dyn Error>> {
+    let mut options = game::Options::from(matches.clone());
+
+    if let Some(path) = matches.get_one::<String>("level") {
+        options.set_level(Level::load(path)?);
+    }
+    game::Game::check_options(&options)?;
+
+    Ok(options)
 }
@@ -0,0 +1,453 @@
+use crate::event::{Event, EventHandle};
+use crate::game::{Direction, Game, Options};
+use crate::highscore::HighScores;
+use crate::interface::{
+    draw_dialog, finish_game, play, restore_terminal, save_unfinished, setup_terminal,
+};
+use crate::mode::Mode;
+use crate::solver::PathAlgorithm;
+use crate::stats::date;
+use crossterm::event::KeyCode;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use tui::layout::Alignment;
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+
+/// Largest width and height of the board in the settings.
+const MAX_SIZE: i32 = 200;
+/// Fastest speed in the settings.
+const MAX_SPEED: f64 = 100.0;
+
+/// The options chosen on the settings screen, stored in the user's config
+/// directory.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub width: i32,
+    pub height: i32,
+    pub speed: f64,
+    pub length: i32,
+    pub direction: Direction,
+    pub borders: bool,
+    pub path_alg: PathAlgorithm,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        let options = Options::default();
+
+        Settings {
+            width: options.width,
+            height: options.height,
+            speed: options.speed,
+            length: options.length,
+            direction: options.direction,
+            borders: options.borders,
+            path_alg: options.path_alg,
+        }
+    }
+}
+
+impl Settings {
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("snake").join("config.toml"))
+    }
+
+    /// Reads the settings, taking the defaults if there are none yet or the
+    /// file can't be read.
+    pub fn load() -> Self {
+        Settings::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|text| toml::from_str(&text).ok())
+            .filter(|settings: &Settings| settings.validate().is_ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        let path = Settings::path().ok_or("no config directory")?;
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, toml::to_string(self)?)?;
+
+        Ok(())
+    }
+
+    /// Options of a game with the settings. The head of the snake starts at
+    /// (3, 3), or further in if the rest of the snake wouldn't fit behind it.
+    pub fn options(&self, self_play: bool) -> Options {
+        let defaults = Options::default();
+        let behind = self.length - 1;
+
+        Options {
+            width: self.width,
+            height: self.height,
+            head_x: match self.direction {
+                Direction::Right => defaults.head_x.max(behind),
+                _ => defaults.head_x,
+            },
+            head_y: match self.direction {
+                Direction::Down => defaults.head_y.max(behind),
+                _ => defaults.head_y,
+            },
+            speed: self.speed,
+            length: self.length,
+            direction: self.direction.clone(),
+            borders: self.borders,
+            self_play,
+            path_alg: self.path_alg.clone(),
+            ..defaults
+        }
+    }
+
+    /// Checks that a game can be started with the settings.
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if !(1..=MAX_SIZE).contains(&self.width) || !(1..=MAX_SIZE).contains(&self.height) {
+            return Err("width and height must be between 1 and 200");
+        }
+
+        if !(1.0..=MAX_SPEED).contains(&self.speed) {
+            return Err("speed must be between 1 and 100");
+        }
+
+        if self.length < 1 {
+            return Err("length must be at least 1");
+        }
+
+        Game::check_options(&self.options(false)).map_err(|_| "the snake doesn't fit on the board")
+    }
+
+    /// Changes the setting by `step`, or to the next or the previous choice
+    /// for the settings that aren't numbers.
+    fn change(&mut self, field: Field, step: i32) {
+        match field {
+            Field::Width => self.width += step,
+            Field::Height => self.height += step,
+            Field::Speed => self.speed += step as f64,
+            Field::Length => self.length += step,
+            Field::Direction => {
+                let dirs = [
+                    Direction::Up,
+                    Direction::Right,
+                    Direction::Down,
+                    Direction::Left,
+                ];
+                let idx = dirs.iter().position(|dir| *dir == self.direction).unwrap();
+                self.direction = dirs[(idx as i32 + step).rem_euclid(4) as usize].clone();
+            }
+            Field::Borders => self.borders = !self.borders,
+            Field::Algorithm => {
+                self.path_alg = match self.path_alg {
+                    PathAlgorithm::AStar => PathAlgorithm::Bfs,
+                    PathAlgorithm::Bfs => PathAlgorithm::AStar,
+                }
+            }
+        }
+    }
+
+    fn value(&self, field: Field) -> String {
+        match field {
+            Field::Width => self.width.to_string(),
+            Field::Height => self.height.to_string(),
+            Field::Speed => self.speed.to_string(),
+            Field::Length => self.length.to_string(),
+            Field::Direction => match self.direction {
+                Direction::Up => "up".to_string(),
+                Direction::Down => "down".to_string(),
+                Direction::Left => "left".to_string(),
+                Direction::Right => "right".to_string(),
+            },
+            Field::Borders => if self.borders { "on" } else { "off" }.to_string(),
+            Field::Algorithm => match self.path_alg {
+                PathAlgorithm::AStar => "astar".to_string(),
+                PathAlgorithm::Bfs => "bfs".to_string(),
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Choice {
+    Play,
+    SelfPlay,
+    Settings,
+    HighScores,
+    Quit,
+}
+
+impl Choice {
+    const ALL: [Choice; 5] = [
+        Choice::Play,
+        Choice::SelfPlay,
+        Choice::Settings,
+        Choice::HighScores,
+        Choice::Quit,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Choice::Play => "Play",
+            Choice::SelfPlay => "Self-play",
+            Choice::Settings => "Settings",
+            Choice::HighScores => "High scores",
+            Choice::Quit => "Quit",
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Field {
+    Width,
+    Height,
+    Speed,
+    Length,
+    Direction,
+    Borders,
+    Algorithm,
+}
+
+impl Field {
+    const ALL: [Field; 7] = [
+        Field::Width,
+        Field::Height,
+        Field::Speed,
+        Field::Length,
+        Field::Direction,
+        Field::Borders,
+        Field::Algorithm,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Field::Width => "Width",
+            Field::Height => "Height",
+            Field::Speed => "Speed",
+            Field::Length => "Length",
+            Field::Direction => "Direction",
+            Field::Borders => "Borders",
+            Field::Algorithm => "Self-play algorithm",
+        }
+    }
+}
+
+enum Screen {
+    Menu,
+    Settings,
+    HighScores,
+}
+
+fn selectable(text: String, selected: bool) -> Spans<'static> {
+    let style = if selected {
+        Style::default().add_modifier(Modifier::REVERSED)
+    } else {
+        Style::default()
+    };
+
+    Spans::from(Span::styled(text, style))
+}
+
+fn menu_lines(selected: usize) -> Vec<Spans<'static>> {
+    let mut lines = vec![Spans::from("")];
+
+    for (idx, choice) in Choice::ALL.iter().enumerate() {
+        lines.push(selectable(
+            format!(" {:^15} ", choice.name()),
+            idx == selected,
+        ));
+    }
+
+    lines.push(Spans::from(""));
+    lines.push(Spans::from("Move: ↑↓ jk ws, Select: enter, Quit: q"));
+
+    lines
+}
+
+fn settings_lines(settings: &Settings, selected: usize) -> Vec<Spans<'static>> {
+    let mut lines = vec![Spans::from("")];
+
+    for (idx, field) in Field::ALL.iter().enumerate() {
+        lines.push(selectable(
+            format!(" {:<20}{:>8} ", field.name(), settings.value(*field)),
+            idx == selected,
+        ));
+    }
+
+    lines.push(Spans::from(""));
+    lines.push(Spans::from("Move: ↑↓ jk ws, Change: ←→ hl, By 10: H L"));
+    lines.push(Spans::from("Back: q enter"));
+
+    lines
+}
+
+fn high_score_lines(high_scores: &HighScores, mode: Mode) -> Vec<Spans<'static>> {
+    let mut lines = vec![
+        Spans::from(""),
+        Spans::from(Span::styled(
+            format!("◀ {:^13} ▶", mode.name()),
+            Style::default().fg(Color::Yellow),
+        )),
+        Spans::from(""),
+    ];
+
+    let table = high_scores.table(mode);
+    if table.is_empty() {
+        lines.push(Spans::from("No scores yet"));
+    }
+
+    for (rank, entry) in table.iter().enumerate() {
+        lines.push(Spans::from(format!(
+            "{:>2}. {:>6}  length {:>4}  {} {}",
+            rank + 1,
+            entry.score,
+            entry.length,
+            date(entry.time),
+            if entry.assisted { "*" } else { " " }
+        )));
+    }
+
+    lines.push(Spans::from(""));
+    lines.push(Spans::from("Mode: ←→ hl, Back: q"));
+
+    lines
+}
+
+/// Shows the start menu until the player quits. Games are started with the
+/// settings, which are saved when leaving the settings screen.
+pub fn run() -> Result<(), Box<dyn Error>> {
+    let mut settings = Settings::load();
+    let mut high_scores = HighScores::load();
+    let mut terminal = setup_terminal()?;
+    let events = EventHandle::without_ticks();
+
+    let mut screen = Screen::Menu;
+    let mut choice = 0;
+    let mut field = 0;
+    let mut mode = 0;
+    let mut message: Option<String> = None;
+
+    loop {
+        terminal.draw(|f| {
+            let (title, mut lines) = match screen {
+                Screen::Menu => ("Snake", menu_lines(choice)),
+                Screen::Settings => ("Settings", settings_lines(&settings, field)),
+                Screen::HighScores => (
+                    "High scores",
+                    high_score_lines(&high_scores, Mode::ALL[mode]),
+                ),
+            };
+
+            if let Some(message) = &message {
+                lines.push(Spans::from(Span::styled(
+                    message.clone(),
+                    Style::default().fg(Color::Yellow),
+                )));
+            }
+
+            draw_dialog(f, title, lines, Alignment::Center);
+        })?;
+
+        message = None;
+
+        let key = match events.next()? {
+            Event::Input(key) => key,
+            _ => continue,
+        };
+
+        match screen {
+            Screen::Menu => match key.code {
+                KeyCode::Char('q') => break,
+                KeyCode::Char('k') | KeyCode::Char('w') | KeyCode::Up => {
+                    choice = choice.saturating_sub(1)
+                }
+                KeyCode::Char('j') | KeyCode::Char('s') | KeyCode::Down => {
+                    choice = (choice + 1).min(Choice::ALL.len() - 1)
+                }
+                KeyCode::Enter | KeyCode::Char(' ') => match Choice::ALL[choice] {
+                    Choice::Play | Choice::SelfPlay => {
+                        let options = settings.options(Choice::ALL[choice] == Choice::SelfPlay);
+
+                        if let Err(err) = Game::check_options(&options) {
+                            message = Some(err.to_string());
+                            continue;
+                        }
+
+                        let mut recorded = None;
+                        let game = play(
+                            &mut terminal,
+                            &events,
+                            &options,
+                            Game::new(&options),
+                            |game, replace| {
+                                finish_game(
+                                    &mut high_scores,
+                                    &mut recorded,
+                                    game,
+                                    &options,
+                                    replace,
+                                )
+                            },
+                        )?;
+                        events.set_speed(0.0);
+
+                        if save_unfinished(&options, game)? {
+                            message = Some("The game is saved, continue it with --resume".into());
+                        }
+                    }
+                    Choice::Settings => screen = Screen::Settings,
+                    Choice::HighScores => screen = Screen::HighScores,
+                    Choice::Quit => break,
+                },
+                _ => {}
+            },
+            Screen::Settings => {
+                let step = match key.code {
+                    KeyCode::Char('h') | KeyCode::Left => -1,
+                    KeyCode::Char('l') | KeyCode::Right => 1,
+                    KeyCode::Char('H') => -10,
+                    KeyCode::Char('L') => 10,
+                    _ => 0,
+                };
+
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc | KeyCode::Enter => {
+                        if let Err(err) = settings.save() {
+                            message = Some(format!("The settings can't be saved: {}", err));
+                        }
+                        screen = Screen::Menu;
+                    }
+                    KeyCode::Char('k') | KeyCode::Char('w') | KeyCode::Up => {
+                        field = field.saturating_sub(1)
+                    }
+                    KeyCode::Char('j') | KeyCode::Char('s') | KeyCode::Down => {
+                        field = (field + 1).min(Field::ALL.len() - 1)
+                    }
+                    _ if step != 0 => {
+                        // changes that make the settings invalid are undone
+                        let mut changed = settings.clone();
+                        changed.change(Field::ALL[field], step);
+
+                        match changed.validate() {
+                            Ok(()) => settings = changed,
+                            Err(err) => message = Some(err.to_string()),
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Screen::HighScores => match key.code {
+                KeyCode::Char('q') | KeyCode::Esc | KeyCode::Enter => screen = Screen::Menu,
+                KeyCode::Char('h') | KeyCode::Left => {
+                    mode = (mode + Mode::ALL.len() - 1) % Mode::ALL.len()
+                }
+                KeyCode::Char('l') | KeyCode::Right => mode = (mode + 1) % Mode::ALL.len(),
+                _ => {}
+            },
+        }
+    }
+
+    restore_terminal()
+}
@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+
+/// Ticks between two walls appearing in survival mode.
+pub const SURVIVAL_WALL_INTERVAL: u64 = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Mode {
+    /// Eat apples until the snake dies.
+    Endless,
+    /// Eat as many apples as possible before the time runs out.
+    TimeAttack,
+    /// Stay alive as long as possible while walls keep appearing. The score
+    /// is the number of ticks survived.
+    Survival,
+    /// The snake can't die. Running into a body cuts it at that segment and
+    /// anything else cuts the snake in half.
+    Zen,
+}
+
+impl Mode {
+    pub const ALL: [Mode; 4] = [Mode::Endless, Mode::TimeAttack, Mode::Survival, Mode::Zen];
+
+    /// Modes whose scores are compared on equal terms, so games can't be
+    /// rewound in them.
+    pub fn is_ranked(&self) -> bool {
+        matches!(self, Mode::TimeAttack | Mode::Survival)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mode::Endless => "endless",
+            Mode::TimeAttack => "time-attack",
+            Mode::Survival => "survival",
+            Mode::Zen => "zen",
+        }
+    }
+}
+
+impl From<&String> for Mode {
+    fn from(mode: &String) -> Self {
+        match mode.as_str() {
+            "endless" => Mode::Endless,
+            "time-attack" => Mode::TimeAttack,
+            "survival" => Mode::Survival,
+            "zen" => Mode::Zen,
+            _ => panic!(),
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use crate::game::{Direction, Point};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpStream;
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Hello { name: String },
+    Ready { ready: bool },
+    Input { direction: Direction },
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Welcome {
+        id: usize,
+        width: i32,
+        height: i32,
+    },
+    Lobby {
+        players: Vec<LobbyPlayer>,
+    },
+    State {
+        tick: u64,
+        players: Vec<PlayerState>,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct LobbyPlayer {
+    pub id: usize,
+    pub name: String,
+    pub ready: bool,
+    /// Score of the last round the player took part in.
+    pub score: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PlayerState {
+    pub id: usize,
+    pub name: String,
+    /// Body of the snake from its tail to its head.
+    pub snake: Vec<Point>,
+    pub apple: Point,
+    pub direction: Direction,
+    pub alive: bool,
+    pub score: usize,
+}
+
+/// Writes the message as a single JSON line.
+pub fn send<T: Serialize>(stream: &mut TcpStream, message: &T) -> io::Result<()> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())
+}
+
+/// Reads JSON lines from the stream until it is closed or `f` returns false.
+/// Lines that can't be parsed are skipped.
+pub fn receive<T: DeserializeOwned>(stream: TcpStream, mut f: impl FnMut(T) -> bool) {
+    for line in BufReader::new(stream).lines().map_while(Result::ok) {
+        if let Ok(message) = serde_json::from_str(&line) {
+            if !f(message) {
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,468 @@
+use crate::game::{Game, Point};
+use crate::item::ItemKind;
+use crate::replay::{Player, Replay};
+use serde::Deserialize;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::Path;
+
+/// Extra hundredths of a second the last frame of a GIF stays on screen.
+const FINAL_FRAME_DELAY: u16 = 200;
+
+type Rgb = [u8; 3];
+
+/// Colors of a rendered board.
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    pub background: Rgb,
+    pub wall: Rgb,
+    /// Colors of the portal pairs, repeated when there are more pairs.
+    pub portals: Vec<Rgb>,
+    pub apple: Rgb,
+    pub hazard: Rgb,
+    /// Head and body colors of the snakes, the first one being the player's.
+    pub snakes: Vec<[Rgb; 2]>,
+    pub golden_apple: Rgb,
+    pub shrink_pill: Rgb,
+    pub slow_motion: Rgb,
+    pub ghost: Rgb,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::dark()
+    }
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Theme {
+            background: [24, 24, 32],
+            wall: [110, 110, 120],
+            portals: vec![
+                [90, 150, 255],
+                [220, 110, 230],
+                [110, 220, 120],
+                [255, 120, 110],
+            ],
+            apple: [220, 40, 40],
+            hazard: [255, 110, 90],
+            snakes: vec![
+                [[60, 110, 230], [230, 200, 60]],
+                [[170, 60, 170], [230, 130, 230]],
+                [[40, 160, 170], [130, 230, 230]],
+                [[40, 150, 60], [130, 230, 130]],
+                [[180, 40, 40], [240, 120, 120]],
+                [[150, 150, 150], [240, 240, 240]],
+            ],
+            golden_apple: [250, 210, 40],
+            shrink_pill: [200, 80, 200],
+            slow_motion: [60, 200, 220],
+            ghost: [235, 235, 235],
+        }
+    }
+
+    pub fn light() -> Self {
+        Theme {
+            background: [245, 242, 232],
+            wall: [90, 90, 100],
+            ghost: [150, 150, 170],
+            ..Theme::dark()
+        }
+    }
+
+    /// Takes one of the built-in themes by name or reads a TOML file giving
+    /// the colors as `[r, g, b]`. Colors missing from the file are taken from
+    /// the dark theme.
+    pub fn load(name: &str) -> Result<Self, Box<dyn Error>> {
+        match name {
+            "dark" => Ok(Theme::dark()),
+            "light" => Ok(Theme::light()),
+            path => {
+                let text = fs::read_to_string(path)?;
+                toml::from_str(&text).map_err(|err| format!("{}: {}", path, err).into())
+            }
+        }
+    }
+
+    fn item(&self, kind: ItemKind) -> Rgb {
+        match kind {
+            ItemKind::GoldenApple => self.golden_apple,
+            ItemKind::ShrinkPill => self.shrink_pill,
+            ItemKind::SlowMotion => self.slow_motion,
+            ItemKind::Ghost => self.ghost,
+        }
+    }
+
+    fn snake(&self, id: usize) -> [Rgb; 2] {
+        match self.snakes.len() {
+            0 => [self.wall; 2],
+            1 => self.snakes[0],
+            _ if id == 0 => self.snakes[0],
+            len => self.snakes[1 + (id - 1) % (len - 1)],
+        }
+    }
+
+    /// Every color of the theme, used as the palette of the images.
+    fn palette(&self) -> Vec<Rgb> {
+        let mut colors = vec![
+            self.background,
+            self.wall,
+            self.apple,
+            self.hazard,
+            self.golden_apple,
+            self.shrink_pill,
+            self.slow_motion,
+            self.ghost,
+        ];
+        colors.extend(&self.portals);
+        colors.extend(self.snakes.iter().flatten());
+
+        let mut palette: Vec<Rgb> = Vec::new();
+        for color in colors {
+            if !palette.contains(&color) {
+                palette.push(color);
+            }
+        }
+        palette.truncate(256);
+
+        palette
+    }
+}
+
+/// A board drawn as pixels holding indices into the palette of the theme.
+struct Canvas {
+    width: usize,
+    height: usize,
+    cell: usize,
+    palette: Vec<Rgb>,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(board: (i32, i32), cell: usize, palette: Vec<Rgb>) -> Self {
+        let width = board.0 as usize * cell;
+        let height = board.1 as usize * cell;
+
+        Canvas {
+            width,
+            height,
+            cell,
+            palette,
+            pixels: vec![0; width * height],
+        }
+    }
+
+    fn index(&self, color: Rgb) -> u8 {
+        self.palette
+            .iter()
+            .position(|other| *other == color)
+            .unwrap_or(0) as u8
+    }
+
+    /// Fills the pixels for which `inside` returns true given their offset
+    /// from the top left corner of the cell and the size of a cell.
+    fn fill_cell(&mut self, p: &Point, color: Rgb, inside: impl Fn(usize, usize, usize) -> bool) {
+        let index = self.index(color);
+        let (left, top) = (p.x as usize * self.cell, p.y as usize * self.cell);
+
+        for y in 0..self.cell {
+            for x in 0..self.cell {
+                if inside(x, y, self.cell) {
+                    self.pixels[(top + y) * self.width + left + x] = index;
+                }
+            }
+        }
+    }
+
+    fn square(&mut self, p: &Point, color: Rgb) {
+        self.fill_cell(p, color, |_, _, _| true);
+    }
+
+    fn circle(&mut self, p: &Point, color: Rgb) {
+        self.fill_cell(p, color, |x, y, cell| {
+            let (dx, dy) = (2 * x + 1, 2 * y + 1);
+            let (dx, dy) = (dx.abs_diff(cell), dy.abs_diff(cell));
+            dx * dx + dy * dy <= cell * cell
+        });
+    }
+
+    fn diamond(&mut self, p: &Point, color: Rgb) {
+        self.fill_cell(p, color, |x, y, cell| {
+            (2 * x + 1).abs_diff(cell) + (2 * y + 1).abs_diff(cell) <= cell
+        });
+    }
+
+    /// Draws a snake segment with a gap around it, joined to the segment
+    /// towards its head if the two are next to each other.
+    fn segment(&mut self, p: &Point, next: Option<&Point>, color: Rgb) {
+        let gap = self.cell / 8;
+        self.fill_cell(p, color, |x, y, cell| {
+            x >= gap && y >= gap && x < cell - gap && y < cell - gap
+        });
+
+        let Some(next) = next.filter(|next| p.manhattan_distance(next) == 1) else {
+            return;
+        };
+
+        // the gap between the two cells
+        let index = self.index(color);
+        let (x0, y0) = (
+            p.x.min(next.x) as usize * self.cell,
+            p.y.min(next.y) as usize * self.cell,
+        );
+        let (w, h) = if p.y == next.y {
+            (2 * self.cell, self.cell)
+        } else {
+            (self.cell, 2 * self.cell)
+        };
+
+        for y in y0 + gap..y0 + h - gap {
+            for x in x0 + gap..x0 + w - gap {
+                self.pixels[y * self.width + x] = index;
+            }
+        }
+    }
+
+    fn draw(&mut self, game: &Game, theme: &Theme) {
+        let background = self.index(theme.background);
+        self.pixels.fill(background);
+
+        for wall in &game.walls {
+            self.square(wall, theme.wall);
+        }
+
+        for (idx, (a, b)) in game.portals.iter().enumerate() {
+            let color = theme
+                .portals
+                .get(idx % theme.portals.len().max(1))
+                .copied()
+                .unwrap_or(theme.wall);
+            self.circle(a, color);
+            self.circle(b, color);
+        }
+
+        self.circle(&game.apple, theme.apple);
+
+        for item in &game.items {
+            self.diamond(&item.position, theme.item(item.kind));
+        }
+
+        for snake in game
+            .snakes
+            .iter()
+            .filter(|snake| snake.alive || snake.id == 0)
+        {
+            let [head_color, body_color] = theme.snake(snake.id);
+
+            for (idx, p) in snake.body.iter().enumerate() {
+                let next = snake.body.get(idx + 1);
+                let color = if next.is_none() {
+                    head_color
+                } else {
+                    body_color
+                };
+                self.segment(p, next, color);
+            }
+        }
+
+        for hazard in &game.hazards {
+            self.diamond(&hazard.position, theme.hazard);
+        }
+    }
+
+    fn flat_palette(&self) -> Vec<u8> {
+        self.palette.iter().flatten().copied().collect()
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum Format {
+    /// One animated GIF.
+    Gif,
+    /// A directory of numbered PNG files, one for every tick.
+    Png,
+}
+
+impl From<&String> for Format {
+    fn from(format: &String) -> Self {
+        match format.as_str() {
+            "gif" => Format::Gif,
+            "png" => Format::Png,
+            _ => panic!(),
+        }
+    }
+}
+
+/// Draws every tick of the replay with square cells of `cell` pixels.
+pub fn render(
+    replay_path: &str,
+    output: &str,
+    format: Format,
+    cell: usize,
+    theme: &Theme,
+) -> Result<(), Box<dyn Error>> {
+    let replay = Replay::load(replay_path)?;
+    let speed = replay.speed;
+    let mut canvas = Canvas::new(replay.start.board, cell.max(1), theme.palette());
+    let mut player = Player::new(replay);
+
+    match format {
+        Format::Gif => {
+            let (width, height) = (u16::try_from(canvas.width)?, u16::try_from(canvas.height)?);
+            let file = BufWriter::new(File::create(output)?);
+            let mut encoder = gif::Encoder::new(file, width, height, &canvas.flat_palette())?;
+            encoder.set_repeat(gif::Repeat::Infinite)?;
+
+            let delay = (100.0 / speed).round().max(1.0) as u16;
+            let mut previous: Option<Vec<u8>> = None;
+
+            for tick in 0..=player.len() {
+                player.seek(tick);
+                canvas.draw(player.game(), theme);
+
+                let mut frame = changed_region(&canvas, previous.as_deref());
+                frame.delay = delay;
+                if tick == player.len() {
+                    frame.delay += FINAL_FRAME_DELAY;
+                }
+                encoder.write_frame(&frame)?;
+
+                previous = Some(canvas.pixels.clone());
+            }
+        }
+        Format::Png => {
+            fs::create_dir_all(output)?;
+
+            for tick in 0..=player.len() {
+                player.seek(tick);
+                canvas.draw(player.game(), theme);
+
+                let path = Path::new(output).join(format!("{:05}.png", tick));
+                let file = BufWriter::new(File::create(path)?);
+                let mut encoder =
+                    png::Encoder::new(file, canvas.width as u32, canvas.height as u32);
+                encoder.set_color(png::ColorType::Indexed);
+                encoder.set_depth(png::BitDepth::Eight);
+                encoder.set_palette(canvas.flat_palette());
+                encoder.write_header()?.write_image_data(&canvas.pixels)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// A GIF frame covering only the pixels that changed since the previous frame,
+/// drawn over it.
+fn changed_region(canvas: &Canvas, previous: Option<&[u8]>) -> gif::Frame<'static> {
+    let width = canvas.width;
+    let changed = |idx: usize| previous.is_none_or(|previous| previous[idx] != canvas.pixels[idx]);
+
+    let (mut left, mut top, mut right, mut bottom) = (width, canvas.height, 0, 0);
+    for y in 0..canvas.height {
+        for x in 0..width {
+            if changed(y * width + x) {
+                left = left.min(x);
+                top = top.min(y);
+                right = right.max(x + 1);
+                bottom = bottom.max(y + 1);
+            }
+        }
+    }
+
+    // nothing changed, but the frame is still needed for its delay
+    if left >= right {
+        (left, top, right, bottom) = (0, 0, 1, 1);
+    }
+
+    let mut pixels = Vec::with_capacity((right - left) * (bottom - top));
+    for y in top..bottom {
+        pixels.extend_from_slice(&canvas.pixels[y * width + left..y * width + right]);
+    }
+
+    let mut frame =
+        gif::Frame::from_indexed_pixels((right - left) as u16, (bottom - top) as u16, pixels, None);
+    frame.left = left as u16;
+    frame.top = top as u16;
+    frame.dispose = gif::DisposalMethod::Keep;
+
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Direction, Options};
+    use std::env;
+    use std::io::BufReader;
+
+    /// Saves a replay of `ticks` moves right on a 6x4 board and returns its path.
+    fn small_replay(name: &str, ticks: usize) -> String {
+        let options = Options {
+            width: 6,
+            height: 4,
+            head_x: 2,
+            head_y: 1,
+            seed: Some(3),
+            ..Options::default()
+        };
+        let mut game = Game::new(&options);
+        let mut replay = Replay::new(&game, options.speed);
+        for _ in 0..ticks {
+            let dirs = [Direction::Right];
+            replay.push(&dirs);
+            game.step(&dirs);
+        }
+
+        let path = env::temp_dir().join(format!("snake-{}-{}.json", name, std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+        replay.save(&path).unwrap();
+        path
+    }
+
+    #[test]
+    fn gif_has_a_frame_for_every_tick() {
+        let replay = small_replay("gif", 3);
+        let output = format!("{}.gif", replay);
+        render(&replay, &output, Format::Gif, 5, &Theme::dark()).unwrap();
+
+        let file = File::open(&output).unwrap();
+        let mut decoder = gif::DecodeOptions::new().read_info(file).unwrap();
+        assert_eq!((decoder.width(), decoder.height()), (30, 20));
+
+        let mut frames = 0;
+        while decoder.read_next_frame().unwrap().is_some() {
+            frames += 1;
+        }
+        fs::remove_file(&replay).unwrap();
+        fs::remove_file(&output).unwrap();
+
+        // the start and every move
+        assert_eq!(frames, 4);
+    }
+
+    #[test]
+    fn png_has_a_file_for_every_tick() {
+        let replay = small_replay("png", 3);
+        let output = format!("{}.png.d", replay);
+        render(&replay, &output, Format::Png, 5, &Theme::dark()).unwrap();
+
+        let mut files: Vec<_> = fs::read_dir(&output)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        files.sort();
+        assert_eq!(files, ["00000.png", "00001.png", "00002.png", "00003.png"]);
+
+        for file in &files {
+            let file = File::open(Path::new(&output).join(file)).unwrap();
+            let reader = png::Decoder::new(BufReader::new(file)).read_info().unwrap();
+            assert_eq!((reader.info().width, reader.info().height), (30, 20));
+        }
+        fs::remove_file(&replay).unwrap();
+        fs::remove_dir_all(&output).unwrap();
+    }
+}
@@ -0,0 +1,337 @@
+use crate::event::{Event, EventHandle};
+use crate::game::{Direction, Game};
+use crate::interface::{draw_board, game_grid, restore_terminal, setup_terminal};
+use crossterm::event::KeyCode;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use tui::style::{Color, Style};
+use tui::text::{Span, Spans, Text};
+
+/// Ticks between two snapshots kept by the player.
+const SNAPSHOT_INTERVAL: usize = 100;
+
+/// Slowest and fastest playback relative to the speed the game was played at.
+const MIN_RATE: f64 = 0.25;
+const MAX_RATE: f64 = 16.0;
+
+/// A recorded game. Games are deterministic, so the game before the first
+/// tick and the directions chosen on every tick are enough to play it again.
+#[derive(Serialize, Deserialize)]
+pub struct Replay {
+    /// Ticks per second the game was played at.
+    pub speed: f64,
+    pub start: Game,
+    /// Directions of every snake on each tick.
+    pub moves: Vec<Vec<Direction>>,
+}
+
+impl Replay {
+    pub fn new(game: &Game, speed: f64) -> Self {
+        Replay {
+            speed,
+            start: game.clone(),
+            moves: Vec::new(),
+        }
+    }
+
+    pub fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+        let text = fs::read_to_string(path)?;
+        serde_json::from_str(&text).map_err(|err| format!("{}: {}", path, err).into())
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn push(&mut self, dirs: &[Direction]) {
+        self.moves.push(dirs.to_vec());
+    }
+
+    /// Drops the moves made after the game, which was rewound to an earlier
+    /// tick.
+    pub fn truncate(&mut self, game: &Game) {
+        self.moves
+            .truncate(game.steps.saturating_sub(self.start.steps) as usize);
+    }
+}
+
+/// Plays a replay forwards and backwards. A snapshot of the game is kept every
+/// [`SNAPSHOT_INTERVAL`] ticks, so seeking only replays the ticks after the
+/// closest snapshot.
+pub struct Player {
+    replay: Replay,
+    snapshots: Vec<Game>,
+    /// Ticks on which the player scored.
+    apples: Vec<usize>,
+    game: Game,
+    tick: usize,
+}
+
+impl Player {
+    pub fn new(replay: Replay) -> Self {
+        let mut snapshots = Vec::new();
+        let mut apples = Vec::new();
+        let mut game = replay.start.clone();
+
+        for (tick, dirs) in replay.moves.iter().enumerate() {
+            if tick % SNAPSHOT_INTERVAL == 0 {
+                snapshots.push(game.clone());
+            }
+
+            let score = game.snakes[0].score;
+            game.step(dirs);
+            if game.snakes[0].score > score {
+                apples.push(tick + 1);
+            }
+        }
+
+        Player {
+            game: replay.start.clone(),
+            replay,
+            snapshots,
+            apples,
+            tick: 0,
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn tick(&self) -> usize {
+        self.tick
+    }
+
+    pub fn len(&self) -> usize {
+        self.replay.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.replay.moves.is_empty()
+    }
+
+    /// Moves to the tick, or to the end if the replay is shorter.
+    pub fn seek(&mut self, tick: usize) {
+        let tick = tick.min(self.len());
+
+        // going back or far ahead starts over from the closest snapshot
+        if tick < self.tick || tick - self.tick > SNAPSHOT_INTERVAL {
+            let idx = tick / SNAPSHOT_INTERVAL;
+
+            match self.snapshots.get(idx) {
+                Some(snapshot) => {
+                    self.game = snapshot.clone();
+                    self.tick = idx * SNAPSHOT_INTERVAL;
+                }
+                None => {
+                    self.game = self.replay.start.clone();
+                    self.tick = 0;
+                }
+            }
+        }
+
+        while self.tick < tick {
+            self.game.step(&self.replay.moves[self.tick]);
+            self.tick += 1;
+        }
+    }
+
+    pub fn next_apple(&self) -> Option<usize> {
+        self.apples.iter().copied().find(|&tick| tick > self.tick)
+    }
+
+    pub fn previous_apple(&self) -> Option<usize> {
+        self.apples.iter().copied().rfind(|&tick| tick < self.tick)
+    }
+}
+
+fn progress_bar(player: &Player) -> Spans<'static> {
+    let width = player.game().board.0 as usize * 2 + 2;
+    let filled = if player.is_empty() {
+        width
+    } else {
+        width * player.tick() / player.len()
+    };
+
+    Spans::from(vec![
+        Span::styled("━".repeat(filled), Style::default().fg(Color::Green)),
+        Span::styled(
+            "─".repeat(width - filled),
+            Style::default().fg(Color::DarkGray),
+        ),
+    ])
+}
+
+/// Shows the replay saved in the file with controls to move through it.
+pub fn view(path: &str) -> Result<(), Box<dyn Error>> {
+    let replay = Replay::load(path)?;
+    let speed = replay.speed;
+    let mut player = Player::new(replay);
+    let mut terminal = setup_terminal()?;
+    let events = EventHandle::without_ticks();
+
+    let mut playing = true;
+    let mut reverse = false;
+    let mut rate = 1.0;
+    // digits typed before jumping to a tick
+    let mut typed = String::new();
+
+    loop {
+        events.set_speed(if playing { speed * rate } else { 0.0 });
+
+        terminal.draw(|f| {
+            let game = player.game();
+            let state = if playing && reverse {
+                "◀◀"
+            } else if playing {
+                "▶"
+            } else {
+                "Paused"
+            };
+            let mut info = vec![
+                Span::styled(state, Style::default().fg(Color::Cyan)),
+                Span::raw(format!(
+                    " | Tick {}/{} | {}× | Score {} | Length {}",
+                    player.tick(),
+                    player.len(),
+                    rate,
+                    game.score(),
+                    game.snake().len()
+                )),
+            ];
+            if !typed.is_empty() {
+                info.push(Span::styled(
+                    format!(" | Go to {}", typed),
+                    Style::default().fg(Color::Yellow),
+                ));
+            }
+
+            let status = Text::from(vec![
+                progress_bar(&player),
+                Spans::from(info),
+                Spans::from(
+                    "Play: space, Step: ←→ hl, Reverse: r, Speed: + -, Go to: [tick]g, \
+                     Apple: a A, End: G, Quit: q",
+                ),
+            ]);
+            let border_color = if game.is_game_over() {
+                Color::Red
+            } else if game.is_won() {
+                Color::Cyan
+            } else if !playing {
+                Color::Yellow
+            } else {
+                Color::Green
+            };
+
+            draw_board(f, game_grid(game), border_color, status);
+        })?;
+
+        match events.next()? {
+            Event::Input(key) => match key.code {
+                KeyCode::Char('q') => break,
+                KeyCode::Char(' ') | KeyCode::Char('p') => playing = !playing,
+                KeyCode::Char('r') => reverse = !reverse,
+                KeyCode::Char('l') | KeyCode::Right => {
+                    playing = false;
+                    player.seek(player.tick() + 1);
+                }
+                KeyCode::Char('h') | KeyCode::Left => {
+                    playing = false;
+                    player.seek(player.tick().saturating_sub(1));
+                }
+                KeyCode::Char('+') | KeyCode::Char('=') => rate = (rate * 2.0).min(MAX_RATE),
+                KeyCode::Char('-') => rate = (rate / 2.0).max(MIN_RATE),
+                KeyCode::Char(c) if c.is_ascii_digit() => typed.push(c),
+                KeyCode::Backspace => {
+                    typed.pop();
+                }
+                KeyCode::Char('g') => {
+                    player.seek(typed.parse().unwrap_or(0));
+                    typed.clear();
+                }
+                KeyCode::Char('G') => player.seek(player.len()),
+                KeyCode::Char('a') => {
+                    if let Some(tick) = player.next_apple() {
+                        player.seek(tick);
+                    }
+                }
+                KeyCode::Char('A') => {
+                    if let Some(tick) = player.previous_apple() {
+                        player.seek(tick);
+                    }
+                }
+                _ => {}
+            },
+            Event::Tick if playing => {
+                if reverse && player.tick() > 0 {
+                    player.seek(player.tick() - 1);
+                } else if !reverse && player.tick() < player.len() {
+                    player.seek(player.tick() + 1);
+                } else {
+                    playing = false;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    restore_terminal()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Options;
+    use crate::solver::Solver;
+
+    /// A replay of the solver playing `ticks` ticks.
+    fn solver_replay(ticks: usize) -> Replay {
+        let options = Options {
+            self_play: true,
+            seed: Some(1),
+            ..Options::default()
+        };
+        let mut game = Game::new(&options);
+        let mut solver = Solver::new(&game);
+        let mut replay = Replay::new(&game, options.speed);
+
+        for _ in 0..ticks {
+            let dirs = [solver.next_direction(&game)];
+            replay.push(&dirs);
+            game.step(&dirs);
+        }
+
+        replay
+    }
+
+    /// The game after playing the first `tick` moves from the start.
+    fn straight(replay: &Replay, tick: usize) -> String {
+        let mut game = replay.start.clone();
+        for dirs in &replay.moves[..tick] {
+            game.step(dirs);
+        }
+
+        serde_json::to_string(&game).unwrap()
+    }
+
+    #[test]
+    fn seeking_matches_a_straight_replay() {
+        let replay = solver_replay(350);
+        let expected: Vec<(usize, String)> = [99, 100, 101, 250, 0, 350, 199, 200, 201]
+            .into_iter()
+            .map(|tick| (tick, straight(&replay, tick)))
+            .collect();
+        let mut player = Player::new(replay);
+
+        // forwards, backwards and across snapshots in both directions
+        for (tick, game) in expected {
+            player.seek(tick);
+
+            assert_eq!(player.tick(), tick);
+            assert_eq!(&serde_json::to_string(player.game()).unwrap(), &game);
+        }
+    }
+}
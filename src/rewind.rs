@@ -0,0 +1,46 @@
+use crate::game::Game;
+use std::collections::VecDeque;
+
+/// The most recent states of a game, dropping the oldest one once it is full.
+pub struct History {
+    states: VecDeque<Game>,
+    capacity: usize,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        History {
+            states: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, game: &Game) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.states.len() == self.capacity {
+            self.states.pop_front();
+        }
+        self.states.push_back(game.clone());
+    }
+
+    /// Goes back `ticks` states, or to the oldest one if there are fewer, and
+    /// returns the game as it was then. The states after it are dropped.
+    pub fn rewind(&mut self, ticks: usize) -> Option<Game> {
+        let idx = self.states.len().saturating_sub(ticks.max(1));
+        let game = self.states.get(idx)?.clone();
+
+        self.states.truncate(idx);
+        Some(game)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.states.clear();
+    }
+}
@@ -0,0 +1,104 @@
+use crate::game::{Direction, Game, Point};
+use crate::solver::Solver;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    /// Heads straight for the apple and keeps going when there is no path.
+    Easy,
+    /// Heads for the apple and moves towards open space when there is no
+    /// path.
+    Normal,
+    /// Only goes for the apple when it won't get trapped on the way and keeps
+    /// away from the heads of snakes it can't beat.
+    Hard,
+}
+
+impl From<&String> for Difficulty {
+    fn from(difficulty: &String) -> Self {
+        match difficulty.as_str() {
+            "easy" => Difficulty::Easy,
+            "normal" => Difficulty::Normal,
+            "hard" => Difficulty::Hard,
+            _ => panic!(),
+        }
+    }
+}
+
+/// A computer controlled snake competing with the player for the apples.
+pub struct Rival {
+    id: usize,
+    solver: Solver,
+    difficulty: Difficulty,
+}
+
+impl Rival {
+    pub fn new(game: &Game, id: usize, difficulty: Difficulty) -> Self {
+        Rival {
+            id,
+            solver: Solver::pathfinder(game),
+            difficulty,
+        }
+    }
+
+    pub fn next_direction(&mut self, game: &Game) -> Direction {
+        let snake = &game.snakes[self.id];
+        if !snake.alive {
+            return snake.dir.clone();
+        }
+
+        let head = snake.head().clone();
+
+        let path = self.solver.find_path(game, &head, &game.apple);
+        let next = path.get(1).cloned();
+
+        if let Some(next) = next {
+            if self.difficulty != Difficulty::Hard || self.is_safe(game, &next) {
+                if let Some(direction) = game.direction_between(&head, &next) {
+                    return direction;
+                }
+            }
+        }
+
+        if self.difficulty == Difficulty::Easy {
+            return snake.dir.clone();
+        }
+
+        let length = snake.body.len();
+        let moves: Vec<Point> = game
+            .neighbors(&head)
+            .filter(|point| !game.check_overlap(point) && !game.hazard_threatens(point, 1))
+            .collect();
+
+        moves
+            .into_iter()
+            .max_by_key(|point| {
+                let threatened = self.is_threatened(game, point);
+                let space = self.solver.reachable_cells(game, point, length + 1);
+                (!threatened, space)
+            })
+            .and_then(|point| game.direction_between(&head, &point))
+            .unwrap_or_else(|| snake.dir.clone())
+    }
+
+    /// Returns true if the cell is neither next to a head that wins a head on
+    /// collision nor leads into a space too small for the snake.
+    fn is_safe(&mut self, game: &Game, point: &Point) -> bool {
+        let length = game.snakes[self.id].body.len();
+
+        !self.is_threatened(game, point)
+            && self.solver.reachable_cells(game, point, length + 1) > length
+    }
+
+    fn is_threatened(&self, game: &Game, point: &Point) -> bool {
+        let length = game.snakes[self.id].body.len();
+
+        game.snakes.iter().any(|other| {
+            other.id != self.id
+                && other.alive
+                && other.body.len() >= length
+                && other.head().manhattan_distance(point) == 1
+        })
+    }
+}
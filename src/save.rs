@@ -0,0 +1,105 @@
+use crate::game::{Game, Options};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A game left before it was over, stored in the user's data directory with
+/// the options it was started with.
+#[derive(Serialize, Deserialize)]
+pub struct SavedGame {
+    pub options: Options,
+    pub game: Game,
+    /// Set once the game is resumed, so that it can't be resumed again.
+    pub consumed: bool,
+}
+
+impl SavedGame {
+    pub fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("snake").join("save.json"))
+    }
+
+    /// Saves the game, replacing the one saved before.
+    pub fn save(options: &Options, game: Game) -> Result<(), Box<dyn Error>> {
+        SavedGame::save_to(
+            &SavedGame::path().ok_or("no data directory")?,
+            options,
+            game,
+        )
+    }
+
+    /// Reads the saved game and marks it consumed before handing it out.
+    pub fn resume() -> Result<(Options, Game), Box<dyn Error>> {
+        SavedGame::resume_from(&SavedGame::path().ok_or("no data directory")?)
+    }
+
+    fn save_to(path: &Path, options: &Options, game: Game) -> Result<(), Box<dyn Error>> {
+        SavedGame {
+            options: options.clone(),
+            game,
+            consumed: false,
+        }
+        .write(path)
+    }
+
+    fn resume_from(path: &Path) -> Result<(Options, Game), Box<dyn Error>> {
+        let text = fs::read_to_string(path).map_err(|_| "there is no saved game")?;
+        let mut saved: SavedGame = serde_json::from_str(&text)
+            .map_err(|err| format!("the saved game can't be read: {}", err))?;
+
+        if saved.consumed {
+            return Err("the saved game was already resumed".into());
+        }
+
+        saved.consumed = true;
+        saved.write(path)?;
+
+        Ok((saved.options, saved.game))
+    }
+
+    fn write(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string(self)?)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Direction;
+    use std::env;
+
+    #[test]
+    fn saved_game_is_resumed_once() {
+        let path = env::temp_dir().join(format!("snake-save-{}.json", std::process::id()));
+        let options = Options {
+            seed: Some(5),
+            ..Options::default()
+        };
+        let mut game = Game::new(&options);
+        for _ in 0..4 {
+            game.move_snake(Direction::Down);
+        }
+
+        SavedGame::save_to(&path, &options, game.clone()).unwrap();
+        let (resumed_options, mut resumed) = SavedGame::resume_from(&path).unwrap();
+        let second = SavedGame::resume_from(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(resumed_options.seed, Some(5));
+        assert_eq!(
+            serde_json::to_string(&resumed).unwrap(),
+            serde_json::to_string(&game).unwrap()
+        );
+        assert!(second.is_err());
+
+        // the apples keep coming from where the random numbers left off
+        game.gen_apple();
+        resumed.gen_apple();
+        assert_eq!(resumed.apple, game.apple);
+    }
+}
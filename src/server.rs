@@ -0,0 +1,280 @@
+use crate::game::{Direction, Game, Options};
+use crate::net::{self, ClientMessage, LobbyPlayer, PlayerState, ServerMessage};
+use rand::Rng;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+enum ServerEvent {
+    Connected(usize, TcpStream),
+    Message(usize, ClientMessage),
+    Disconnected(usize),
+}
+
+struct Client {
+    name: String,
+    stream: TcpStream,
+    ready: bool,
+    /// Id of the client's snake in the round, if it plays in it.
+    snake: Option<usize>,
+    dir: Direction,
+    score: Option<usize>,
+}
+
+/// Authoritative server for multiplayer rounds. Every player gets a snake on
+/// one shared board and the server moves all of them on its own clock.
+/// Players wait in the lobby until everyone is ready, and those who join
+/// during a round watch until the next one starts.
+pub struct Server {
+    options: Options,
+    min_players: usize,
+    clients: BTreeMap<usize, Client>,
+    /// Game of the round being played.
+    game: Option<Game>,
+}
+
+pub fn run(options: Options, port: u16, min_players: usize) -> Result<(), Box<dyn Error>> {
+    let listener = TcpListener::bind(("0.0.0.0", port))?;
+    println!("listening on {}", listener.local_addr()?);
+
+    Server::new(options, min_players).serve(listener)
+}
+
+impl Server {
+    pub fn new(options: Options, min_players: usize) -> Self {
+        Server {
+            options,
+            min_players: min_players.max(1),
+            clients: BTreeMap::new(),
+            game: None,
+        }
+    }
+
+    pub fn serve(mut self, listener: TcpListener) -> Result<(), Box<dyn Error>> {
+        let (tx, rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            for (id, stream) in listener.incoming().filter_map(Result::ok).enumerate() {
+                let Ok(reader) = stream.try_clone() else {
+                    continue;
+                };
+
+                if tx.send(ServerEvent::Connected(id, stream)).is_err() {
+                    return;
+                }
+
+                let tx = tx.clone();
+                thread::spawn(move || {
+                    net::receive(reader, |message| {
+                        tx.send(ServerEvent::Message(id, message)).is_ok()
+                    });
+                    let _ = tx.send(ServerEvent::Disconnected(id));
+                });
+            }
+        });
+
+        let tick = Duration::from_micros(f64::floor(1_000_000.0 / self.options.speed) as u64);
+        let mut next_tick = Instant::now() + tick;
+
+        loop {
+            match rx.recv_timeout(next_tick.saturating_duration_since(Instant::now())) {
+                Ok(event) => self.handle(event),
+                Err(RecvTimeoutError::Timeout) => {
+                    next_tick += tick;
+
+                    if self.game.is_some() {
+                        self.step();
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            }
+        }
+    }
+
+    fn handle(&mut self, event: ServerEvent) {
+        match event {
+            ServerEvent::Connected(id, mut stream) => {
+                let _ = stream.set_write_timeout(Some(Duration::from_secs(1)));
+                let welcome = ServerMessage::Welcome {
+                    id,
+                    width: self.options.width,
+                    height: self.options.height,
+                };
+
+                if net::send(&mut stream, &welcome).is_ok() {
+                    self.clients.insert(
+                        id,
+                        Client {
+                            name: format!("player {}", id),
+                            stream,
+                            ready: false,
+                            snake: None,
+                            dir: self.options.direction.clone(),
+                            score: None,
+                        },
+                    );
+                    self.broadcast_lobby();
+                }
+            }
+            ServerEvent::Message(id, message) => {
+                let Some(client) = self.clients.get_mut(&id) else {
+                    return;
+                };
+
+                match message {
+                    ClientMessage::Hello { name } => {
+                        println!("{} joined as player {}", name, id);
+                        client.name = name;
+                        self.broadcast_lobby();
+                    }
+                    ClientMessage::Ready { ready } if self.game.is_none() => {
+                        client.ready = ready;
+                        self.broadcast_lobby();
+                        self.try_start();
+                    }
+                    ClientMessage::Input { direction } => client.dir = direction,
+                    _ => (),
+                }
+            }
+            ServerEvent::Disconnected(id) => {
+                if let Some(client) = self.clients.remove(&id) {
+                    println!("{} left", client.name);
+                }
+
+                if self.game.is_some() {
+                    self.check_round_over();
+                } else {
+                    self.broadcast_lobby();
+                    self.try_start();
+                }
+            }
+        }
+    }
+
+    fn try_start(&mut self) {
+        let ready = self.clients.values().filter(|client| client.ready).count();
+
+        if ready < self.min_players || ready != self.clients.len() {
+            return;
+        }
+
+        let mut options = self.options.clone();
+        options.seed = Some(
+            self.options
+                .seed
+                .unwrap_or_else(|| rand::thread_rng().gen()),
+        );
+        options.rivals = 0;
+        println!("starting a round with {} players", ready);
+
+        // the first player gets the snake the options describe and the others
+        // are spawned around it, a player without room watches the round
+        let mut game = Game::new(&options);
+        for (idx, client) in self.clients.values_mut().enumerate() {
+            client.snake = match idx {
+                0 => Some(0),
+                _ => game.spawn_snake(options.length as usize),
+            };
+            client.dir = match client.snake {
+                Some(id) => game.snakes[id].dir.clone(),
+                None => options.direction.clone(),
+            };
+        }
+
+        self.game = Some(game);
+        self.broadcast_state();
+    }
+
+    fn step(&mut self) {
+        let Some(game) = self.game.as_mut() else {
+            return;
+        };
+
+        let mut dirs: Vec<Direction> = game.snakes.iter().map(|snake| snake.dir.clone()).collect();
+        for client in self.clients.values() {
+            if let Some(id) = client.snake {
+                dirs[id] = client.dir.clone();
+            }
+        }
+        game.step(&dirs);
+
+        self.broadcast_state();
+        self.check_round_over();
+    }
+
+    /// Ends the round once every snake died or the game ended for everyone,
+    /// by filling the board, completing the level or running out of time.
+    fn check_round_over(&mut self) {
+        let Some(game) = self.game.as_ref() else {
+            return;
+        };
+
+        let over = game.snakes.iter().all(|snake| !snake.alive)
+            || game.is_won()
+            || game.is_completed()
+            || game.remaining_ticks() == Some(0);
+
+        if !over {
+            return;
+        }
+
+        for client in self.clients.values_mut() {
+            if let Some(id) = client.snake.take() {
+                client.score = Some(game.snakes[id].score);
+            }
+            client.ready = false;
+        }
+
+        self.game = None;
+        println!("round over");
+        self.broadcast_lobby();
+    }
+
+    fn broadcast_lobby(&mut self) {
+        let players = self
+            .clients
+            .iter()
+            .map(|(id, client)| LobbyPlayer {
+                id: *id,
+                name: client.name.clone(),
+                ready: client.ready,
+                score: client.score,
+            })
+            .collect();
+
+        self.broadcast(&ServerMessage::Lobby { players });
+    }
+
+    fn broadcast_state(&mut self) {
+        let Some(game) = self.game.as_ref() else {
+            return;
+        };
+
+        let players = self
+            .clients
+            .iter()
+            .filter_map(|(id, client)| Some((id, client, &game.snakes[client.snake?])))
+            .map(|(id, client, snake)| PlayerState {
+                id: *id,
+                name: client.name.clone(),
+                snake: snake.body.iter().cloned().collect(),
+                apple: game.apple.clone(),
+                direction: snake.dir.clone(),
+                alive: snake.alive,
+                score: snake.score,
+            })
+            .collect();
+        let tick = game.ticks;
+
+        self.broadcast(&ServerMessage::State { tick, players });
+    }
+
+    /// Sends the message to every client, dropping the ones that can't keep up.
+    fn broadcast(&mut self, message: &ServerMessage) {
+        self.clients
+            .retain(|_, client| net::send(&mut client.stream, message).is_ok());
+    }
+}
@@ -1,7 +1,13 @@
 use crate::game::{Direction, Game, Point};
+use crate::mode::Mode;
+use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 use std::collections::{BinaryHeap, VecDeque};
 
+/// Ticks before and after a hazard is predicted on a cell during which the
+/// search for the tail doesn't count on passing it.
+const HAZARD_MARGIN: usize = 1;
+
 #[derive(Clone, Eq, PartialEq)]
 struct AStarCost {
     coord: Point,
@@ -30,8 +36,12 @@ impl PartialOrd for AStarCost {
 pub struct Cell {
     parent: Option<Point>,
     visited: bool,
+    closed: bool,
     distance: i32,
     circuit_idx: usize,
+    search: u32,
+    /// Set on the player's body by [`Solver::tail_distance`].
+    body: bool,
 }
 
 impl Cell {
@@ -39,13 +49,17 @@ impl Cell {
         Self {
             parent: None,
             visited: false,
+            closed: false,
             distance: i32::MAX,
             circuit_idx: 0,
+            search: 0,
+            body: false,
         }
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum PathAlgorithm {
     AStar,
     Bfs,
@@ -61,35 +75,64 @@ impl From<&String> for PathAlgorithm {
     }
 }
 
-pub struct Solver<'a> {
-    pub game_area: Vec<Vec<Cell>>,
-    game: &'a Game,
+/// Hamiltonian cycle solver for the self playing mode.
+///
+/// Walls and portals break the cycle, so on such boards and in survival mode
+/// the solver heads for the apple whenever it can still reach its tail once
+/// there, and follows its tail otherwise. When a hazard blocks the cycle the
+/// snake leaves it the same way until it ate the next apple. Paths avoid the
+/// cells hazards are predicted to be on by the time the snake gets there.
+///
+/// The solver is built once per game and keeps its grid and search buffers
+/// between ticks. Cells are stamped with the id of the search that last
+/// touched them, so a new search doesn't need to reset the whole grid.
+pub struct Solver {
+    game_area: Vec<Vec<Cell>>,
+    path_alg: PathAlgorithm,
+    search: u32,
+    queue: VecDeque<Point>,
+    open_list: BinaryHeap<AStarCost>,
+    path: Vec<Point>,
+    /// Moves considered by the fallback.
+    moves: Vec<Point>,
+    cycle: bool,
+    /// Length of the snake when it left the cycle to let a hazard pass. It
+    /// heads for the apple on its own until it ate it.
+    detour: Option<usize>,
 }
 
-impl<'a> Solver<'a> {
-    pub fn new(game: &'a Game, game_area: Option<Vec<Vec<Cell>>>) -> Self {
-        let flag = game_area.is_none();
-        let game_area = if let Some(mut game_area) = game_area {
-            for row in game_area.iter_mut() {
-                for mut column in row {
-                    column.parent = None;
-                    column.distance = i32::MAX;
-                }
-            }
-            game_area
-        } else {
-            vec![vec![Cell::new(); game.board.0 as usize]; game.board.1 as usize]
-        };
+impl Solver {
+    pub fn new(game: &Game) -> Self {
+        let mut solver = Solver::pathfinder(game);
 
-        let mut solver = Self { game_area, game };
+        let open_board = game.walls.is_empty() && game.portals.is_empty();
 
-        if flag {
-            solver.build_cycle();
+        if open_board && game.mode != Mode::Survival {
+            solver.build_cycle(game);
+            solver.cycle = true;
         }
 
         solver
     }
 
+    /// Creates a solver that is only used for finding paths, without building
+    /// the cycle.
+    pub fn pathfinder(game: &Game) -> Self {
+        let (width, height) = (game.board.0 as usize, game.board.1 as usize);
+
+        Self {
+            game_area: vec![vec![Cell::new(); width]; height],
+            path_alg: game.path_alg.clone(),
+            search: 0,
+            queue: VecDeque::with_capacity(width * height),
+            open_list: BinaryHeap::with_capacity(width * height),
+            path: Vec::with_capacity(width * height),
+            moves: Vec::with_capacity(4),
+            cycle: false,
+            detour: None,
+        }
+    }
+
     fn get_cell(&self, coord: &Point) -> &Cell {
         &self.game_area[coord.y as usize][coord.x as usize]
     }
@@ -98,8 +141,39 @@ impl<'a> Solver<'a> {
         &mut self.game_area[coord.y as usize][coord.x as usize]
     }
 
-    pub fn find_longest_path(&mut self, destination: &Point) -> Vec<Point> {
-        let mut path = self.find_shortest_path(destination);
+    /// Returns the cell with its search state reset if it wasn't touched by
+    /// the current search yet.
+    fn get_search_cell(&mut self, coord: &Point) -> &mut Cell {
+        let search = self.search;
+        let cell = self.get_mut_cell(coord);
+
+        if cell.search != search {
+            cell.search = search;
+            cell.parent = None;
+            cell.closed = false;
+            cell.distance = i32::MAX;
+            cell.body = false;
+        }
+
+        cell
+    }
+
+    fn begin_search(&mut self) {
+        if self.search == u32::MAX {
+            for cell in self.game_area.iter_mut().flatten() {
+                cell.search = 0;
+            }
+            self.search = 0;
+        }
+
+        self.search += 1;
+        self.path.clear();
+    }
+
+    fn find_longest_path(&mut self, game: &Game, destination: &Point) -> Vec<Point> {
+        self.find_shortest_path(game, destination);
+        // copied so that the buffer keeps its capacity for later searches
+        let mut path = self.path.clone();
         if path.is_empty() {
             return path;
         }
@@ -114,18 +188,20 @@ impl<'a> Solver<'a> {
             let current = path[idx].clone();
             let next = path[idx + 1].clone();
 
-            let direction = current.direction_to(&next).unwrap();
-            let directions_to_test = match direction {
-                Direction::Up | Direction::Down => [Direction::Left, Direction::Right],
-                Direction::Left | Direction::Right => [Direction::Up, Direction::Down],
+            // Steps through a portal or around the board can't be extended.
+            let directions_to_test = match current.direction_to(&next) {
+                Some(Direction::Up | Direction::Down) => &[Direction::Left, Direction::Right][..],
+                Some(Direction::Left | Direction::Right) => &[Direction::Up, Direction::Down],
+                None => &[],
             };
 
             let mut extended = false;
             for test_direction in directions_to_test {
-                let current_test = current.adjacent_point(&test_direction);
-                let next_test = next.adjacent_point(&test_direction);
+                let current_test = current.adjacent_point(test_direction);
+                let next_test = next.adjacent_point(test_direction);
 
-                if self.validate_point(&current_test) && self.validate_point(&next_test) {
+                if self.validate_point(game, &current_test) && self.validate_point(game, &next_test)
+                {
                     self.get_mut_cell(&current_test).visited = true;
                     self.get_mut_cell(&next_test).visited = true;
                     path.insert(idx + 1, current_test);
@@ -147,55 +223,88 @@ impl<'a> Solver<'a> {
         path
     }
 
-    pub fn find_shortest_path(&mut self, destination: &Point) -> Vec<Point> {
-        match self.game.path_alg {
-            PathAlgorithm::AStar => self.find_shortest_path_astar(destination),
-            PathAlgorithm::Bfs => self.find_shortest_path_bfs(destination),
+    /// Finds the shortest path from the head of the snake to `destination`.
+    /// The returned path starts with the head and is empty if the destination
+    /// is unreachable.
+    pub fn find_shortest_path(&mut self, game: &Game, destination: &Point) -> &[Point] {
+        self.find_path(game, game.snake().back().unwrap(), destination)
+    }
+
+    /// Finds the shortest path between two points avoiding every snake on the
+    /// board.
+    pub fn find_path(&mut self, game: &Game, start: &Point, destination: &Point) -> &[Point] {
+        self.begin_search();
+
+        match self.path_alg {
+            PathAlgorithm::AStar => self.find_shortest_path_astar(game, start, destination),
+            PathAlgorithm::Bfs => self.find_shortest_path_bfs(game, start, destination),
         }
+
+        &self.path
     }
 
-    fn find_shortest_path_bfs(&mut self, destination: &Point) -> Vec<Point> {
-        let head = self.game.snake.back().unwrap();
-        self.get_mut_cell(head).distance = 0;
-        let mut queue = VecDeque::new();
-        queue.push_back(head.clone());
+    /// Counts the cells reachable from `start` without crossing a snake,
+    /// stopping once `limit` cells are found.
+    pub fn reachable_cells(&mut self, game: &Game, start: &Point, limit: usize) -> usize {
+        self.begin_search();
+        self.get_search_cell(start).closed = true;
+        self.queue.clear();
+        self.queue.push_back(start.clone());
+        let mut count = 0;
 
-        while !queue.is_empty() {
-            let current_coord = queue.pop_front().unwrap();
+        while let Some(current_coord) = self.queue.pop_front() {
+            count += 1;
 
+            if count >= limit {
+                break;
+            }
+
+            for point in game.neighbors(&current_coord) {
+                if !game.check_overlap(&point) {
+                    let adj_cell = self.get_search_cell(&point);
+
+                    if !adj_cell.closed {
+                        adj_cell.closed = true;
+                        self.queue.push_back(point);
+                    }
+                }
+            }
+        }
+
+        count
+    }
+
+    fn find_shortest_path_bfs(&mut self, game: &Game, head: &Point, destination: &Point) {
+        self.get_search_cell(head).distance = 0;
+        self.queue.clear();
+        self.queue.push_back(head.clone());
+
+        while let Some(current_coord) = self.queue.pop_front() {
             if current_coord == *destination {
                 return self.traverse_path(destination);
             }
 
-            let adj_points = self.get_adj_coords(&current_coord);
+            let dist = self.get_search_cell(&current_coord).distance + 1;
 
-            for point in adj_points {
-                if !self.game.check_overlap(&point) {
-                    let dist = self.get_cell(&current_coord).distance + 1;
-                    let adj_cell = self.get_mut_cell(&point);
+            for point in game.neighbors(&current_coord) {
+                if !is_blocked(game, &point, dist) {
+                    let adj_cell = self.get_search_cell(&point);
 
                     if adj_cell.distance == i32::MAX {
                         adj_cell.parent = Some(current_coord.clone());
                         adj_cell.distance = dist;
-                        queue.push_back(point);
+                        self.queue.push_back(point);
                     }
                 }
             }
         }
-
-        Vec::new()
     }
 
-    fn find_shortest_path_astar(&mut self, destination: &Point) -> Vec<Point> {
-        let mut open_list = BinaryHeap::new();
-        let mut closed_list =
-            vec![vec![false; self.game.board.0 as usize]; self.game.board.1 as usize];
-
-        let head = self.game.snake.back().unwrap();
-        let head_cell = self.get_mut_cell(head);
-        head_cell.distance = 0;
+    fn find_shortest_path_astar(&mut self, game: &Game, head: &Point, destination: &Point) {
+        self.get_search_cell(head).distance = 0;
+        self.open_list.clear();
 
-        open_list.push(AStarCost::new(
+        self.open_list.push(AStarCost::new(
             head.clone(),
             head.manhattan_distance(destination) as i32,
         ));
@@ -203,9 +312,9 @@ impl<'a> Solver<'a> {
         while let Some(AStarCost {
             coord: current_coord,
             ..
-        }) = open_list.pop()
+        }) = self.open_list.pop()
         {
-            if closed_list[current_coord.y as usize][current_coord.x as usize] {
+            if self.get_search_cell(&current_coord).closed {
                 continue;
             }
 
@@ -213,136 +322,333 @@ impl<'a> Solver<'a> {
                 return self.traverse_path(destination);
             }
 
-            closed_list[current_coord.y as usize][current_coord.x as usize] = true;
+            let current_cell = self.get_search_cell(&current_coord);
+            current_cell.closed = true;
+            let distance = current_cell.distance + 1;
 
-            let adj_points = self.get_adj_coords(&current_coord);
+            for point in game.neighbors(&current_coord) {
+                if !is_blocked(game, &point, distance) {
+                    let adj_cell = self.get_search_cell(&point);
 
-            for point in adj_points {
-                if !self.game.check_overlap(&point) {
-                    let distance = self.get_cell(&current_coord).distance + 1;
-                    let adj_cell = self.get_mut_cell(&point);
-
-                    if distance < adj_cell.distance
-                        || !closed_list[point.y as usize][point.x as usize]
-                    {
+                    if distance < adj_cell.distance || !adj_cell.closed {
                         adj_cell.parent = Some(current_coord.clone());
                         adj_cell.distance = distance;
                         let f_score = distance + point.manhattan_distance(destination) as i32;
-                        open_list.push(AStarCost::new(point, f_score))
+                        self.open_list.push(AStarCost::new(point, f_score))
                     }
                 }
             }
         }
-
-        Vec::new()
     }
 
-    pub fn traverse_path(&self, destination: &Point) -> Vec<Point> {
-        let mut path = Vec::new();
+    fn traverse_path(&mut self, destination: &Point) {
         let mut current_coord = destination.clone();
 
         loop {
-            path.push(current_coord.clone());
+            let parent = self.get_cell(&current_coord).parent.clone();
+            self.path.push(current_coord);
 
-            if let Some(parent_cell_coord) = &self.get_cell(&current_coord).parent {
-                current_coord = parent_cell_coord.clone();
+            if let Some(parent_cell_coord) = parent {
+                current_coord = parent_cell_coord;
             } else {
                 break;
             }
         }
 
-        path.reverse();
-        path
+        self.path.reverse();
     }
 
-    fn build_cycle(&mut self) {
-        let path = self.find_longest_path(self.game.snake.front().unwrap());
-        let snake_length = self.game.snake.len();
+    fn build_cycle(&mut self, game: &Game) {
+        let path = self.find_longest_path(game, game.snake().front().unwrap());
+        let snake_length = game.snake().len();
 
         path.iter()
-            .chain(self.game.snake.range(1..snake_length - 1))
+            .chain(game.snake().range(1..snake_length - 1))
             .enumerate()
             .for_each(|(count, point)| self.get_mut_cell(point).circuit_idx = count);
     }
 
-    fn distance_to_tail(&self, mut checked_idx: usize) -> usize {
-        let tail_idx = self.get_cell(self.game.snake.front().unwrap()).circuit_idx;
+    fn distance_to_tail(&self, game: &Game, mut checked_idx: usize) -> usize {
+        let tail_idx = self.get_cell(game.snake().front().unwrap()).circuit_idx;
 
         if tail_idx > checked_idx {
-            checked_idx += self.game.board_size() as usize;
+            checked_idx += game.board_size() as usize;
         }
 
         checked_idx - tail_idx
     }
 
-    pub fn next_direction(&mut self) -> Direction {
-        let head_coord = self.game.snake.back().unwrap();
-        let cur_idx = self.get_cell(head_coord).circuit_idx;
-
-        let adj_points = self.get_adj_coords(head_coord);
-        let mut next_coord = Point::new(0, 0);
-
-        if self.game.snake.len() < (self.game.board_size()) as usize / 2 {
-            let path = self.find_shortest_path(&self.game.apple);
+    pub fn next_direction(&mut self, game: &Game) -> Direction {
+        if !self.cycle || self.detour == Some(game.snake().len()) {
+            return self.fallback_direction(game);
+        }
+        self.detour = None;
 
-            if !path.is_empty() {
-                let head_idx = self.get_cell(&path[0]).circuit_idx;
-                let next_idx = self.get_cell(&path[1]).circuit_idx;
-                let apple_idx = self.get_cell(&self.game.apple).circuit_idx;
+        let head_coord = game.snake().back().unwrap();
+        let cur_idx = self.get_cell(head_coord).circuit_idx;
 
-                let head_idx_rel = self.distance_to_tail(head_idx);
-                let next_idx_rel = self.distance_to_tail(next_idx);
-                let apple_idx_rel = self.distance_to_tail(apple_idx);
-                if next_idx_rel > head_idx_rel && next_idx_rel <= apple_idx_rel {
-                    return head_coord.direction_to(&path[1]).unwrap();
+        if game.snake().len() < (game.board_size()) as usize / 2
+            && !self.find_shortest_path(game, &game.apple).is_empty()
+        {
+            let head_idx = self.get_cell(&self.path[0]).circuit_idx;
+            let next_idx = self.get_cell(&self.path[1]).circuit_idx;
+            let apple_idx = self.get_cell(&game.apple).circuit_idx;
+
+            let head_idx_rel = self.distance_to_tail(game, head_idx);
+            let next_idx_rel = self.distance_to_tail(game, next_idx);
+            let apple_idx_rel = self.distance_to_tail(game, apple_idx);
+            let next = self.path[1].clone();
+            if next_idx_rel > head_idx_rel
+                && next_idx_rel <= apple_idx_rel
+                && self.is_safe_step(game, &next)
+            {
+                if let Some(direction) = game.direction_between(head_coord, &next) {
+                    return direction;
                 }
             }
         }
 
-        for point in adj_points {
+        for point in game.neighbors(head_coord) {
             if self.get_cell(&point).circuit_idx == cur_idx + 1
-                || 1 + cur_idx as i32 == self.game.board_size()
-                    && self.get_cell(&point).circuit_idx == 0
+                || 1 + cur_idx as i32 == game.board_size() && self.get_cell(&point).circuit_idx == 0
             {
-                next_coord = point;
-                break;
+                // the snake leaves the cycle to let a hazard pass
+                if !self.is_safe_step(game, &point) {
+                    self.detour = Some(game.snake().len());
+                    break;
+                }
+
+                if let Some(direction) = game.direction_between(head_coord, &point) {
+                    return direction;
+                }
             }
         }
 
-        head_coord.direction_to(&next_coord).unwrap()
+        self.fallback_direction(game)
     }
 
-    fn get_adj_coords(&self, point: &Point) -> Vec<Point> {
-        let mut adj_points = Vec::new();
-
-        let x = point.x;
-        let y = point.y;
+    /// Returns true if the snake can move onto `point` without being trapped.
+    /// Detours around hazards mix up the order of the body on the cycle, so
+    /// with hazards the tail has to stay reachable too.
+    fn is_safe_step(&mut self, game: &Game, point: &Point) -> bool {
+        !is_blocked(game, point, 1)
+            && (game.hazards.is_empty()
+                || self
+                    .tail_distance(game, std::slice::from_ref(point))
+                    .is_some())
+    }
 
-        if x != 0 {
-            adj_points.push(Point::new(x - 1, y))
+    /// Heads for the apple if the snake can still reach its tail once it ate
+    /// it, otherwise follows its tail the long way round. When every move is
+    /// threatened by a hazard, it takes the one least likely to be fatal.
+    fn fallback_direction(&mut self, game: &Game) -> Direction {
+        let head_coord = game.snake().back().unwrap().clone();
+        let length = game.snake().len();
+
+        if self.find_shortest_path(game, &game.apple).len() > 1 {
+            // the path is taken while the search below borrows the solver
+            let path = std::mem::take(&mut self.path);
+            let safe = self.tail_distance(game, &path[1..]).is_some();
+            let next = path[1].clone();
+            self.path = path;
+
+            if safe {
+                if let Some(direction) = game.direction_between(&head_coord, &next) {
+                    return direction;
+                }
+            }
         }
 
-        if y != 0 {
-            adj_points.push(Point::new(x, y - 1))
+        // the buffer is taken while the searches below borrow the solver
+        let mut moves = std::mem::take(&mut self.moves);
+        moves.clear();
+        moves.extend(
+            game.neighbors(&head_coord)
+                .filter(|point| !is_blocked(game, point, 1)),
+        );
+
+        let best = moves
+            .iter()
+            .filter_map(|point| {
+                Some((
+                    self.tail_distance(game, std::slice::from_ref(point))?,
+                    point,
+                ))
+            })
+            .max_by_key(|(distance, _)| *distance)
+            .map(|(_, point)| point)
+            .or_else(|| {
+                moves
+                    .iter()
+                    .max_by_key(|point| self.reachable_cells(game, point, length + 1))
+            })
+            .and_then(|point| game.direction_between(&head_coord, point));
+
+        // every move is threatened, so any move that isn't certainly fatal
+        // is better than running into a body or off the board
+        let best = best.or_else(|| {
+            moves.clear();
+            moves.extend(
+                game.neighbors(&head_coord)
+                    .filter(|point| !game.check_overlap(point)),
+            );
+
+            moves
+                .iter()
+                .max_by_key(|point| {
+                    (
+                        !game.hazard_at(point, 1),
+                        self.reachable_cells(game, point, length + 1),
+                    )
+                })
+                .and_then(|point| game.direction_between(&head_coord, point))
+        });
+        self.moves = moves;
+
+        best.unwrap_or_else(|| game.dir().clone())
+    }
+
+    /// Returns the distance from the head to the tail of the player's snake
+    /// once it made the moves in `steps`, or `None` if the tail can't be
+    /// reached anymore. The snake grows if the last step is onto the apple,
+    /// and cells hazards are about to cross are avoided.
+    fn tail_distance(&mut self, game: &Game, steps: &[Point]) -> Option<usize> {
+        let body = game.snake();
+        let length = body.len() + (steps.last() == Some(&game.apple)) as usize;
+        let skipped = body.len() + steps.len() - length;
+        let head = steps.last().unwrap_or_else(|| body.back().unwrap());
+        let tail = body.iter().chain(steps).nth(skipped).unwrap();
+
+        if head == tail {
+            return Some(0);
         }
 
-        if x as usize != self.game_area[0].len() - 1 {
-            adj_points.push(Point::new(x + 1, y))
+        self.begin_search();
+        for point in body {
+            self.get_search_cell(point).body = true;
+        }
+        for point in body.iter().chain(steps).skip(skipped) {
+            self.get_search_cell(point).closed = true;
         }
 
-        if y as usize != self.game_area.len() - 1 {
-            adj_points.push(Point::new(x, y + 1))
+        self.get_search_cell(head).distance = 0;
+        self.queue.clear();
+        self.queue.push_back(head.clone());
+
+        while let Some(current_coord) = self.queue.pop_front() {
+            let distance = self.get_search_cell(&current_coord).distance + 1;
+
+            for point in game.neighbors(&current_coord) {
+                if point == *tail {
+                    return Some(distance as usize);
+                }
+
+                // cells the snake left are free again, other bodies are not
+                let overlap = game.check_overlap(&point);
+                let ticks = steps.len() + distance as usize;
+                let threatened = (ticks.saturating_sub(HAZARD_MARGIN)..=ticks + HAZARD_MARGIN)
+                    .any(|t| game.hazard_at(&point, t));
+                let cell = self.get_search_cell(&point);
+
+                if !cell.closed && (!overlap || cell.body) && !threatened {
+                    cell.closed = true;
+                    cell.distance = distance;
+                    self.queue.push_back(point);
+                }
+            }
         }
 
-        adj_points
+        None
     }
 
-    fn validate_point(&self, point: &Point) -> bool {
-        !self.game.check_overlap(point)
-            && point.x >= 0
-            && point.x < self.game.board.0
+    fn validate_point(&self, game: &Game, point: &Point) -> bool {
+        point.x >= 0
+            && point.x < game.board.0
             && point.y >= 0
-            && point.y < self.game.board.1
+            && point.y < game.board.1
+            && !game.check_overlap(point)
             && !self.get_cell(point).visited
     }
 }
+
+/// Returns true if a snake can't enter `point` in `ticks` ticks from now.
+fn is_blocked(game: &Game, point: &Point, ticks: i32) -> bool {
+    game.check_overlap(point) || game.hazard_threatens(point, ticks as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Options;
+    use crate::level::Level;
+
+    /// Plays the game with the solver for at most `ticks` ticks.
+    fn self_play(options: &Options, ticks: u64) -> Game {
+        let mut game = Game::new(options);
+        let mut solver = Solver::new(&game);
+
+        while game.is_running() && game.ticks < ticks {
+            let dir = solver.next_direction(&game);
+            game.move_snake(dir);
+        }
+
+        game
+    }
+
+    #[test]
+    fn survives_hazards() {
+        for seed in 0..5 {
+            let options = Options {
+                self_play: true,
+                hazards: 3,
+                seed: Some(seed),
+                ..Options::default()
+            };
+            let game = self_play(&options, 2000);
+
+            assert!(game.is_running(), "seed {}: {:?}", seed, game.cause());
+        }
+    }
+
+    #[test]
+    fn completes_gauntlet() {
+        let level = Level::load(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/levels/06-gauntlet.txt"
+        ))
+        .unwrap();
+
+        for seed in 0..3 {
+            let mut options = Options {
+                self_play: true,
+                seed: Some(seed),
+                ..Options::default()
+            };
+            options.set_level(level.clone());
+            let game = self_play(&options, 20_000);
+
+            assert!(game.is_completed(), "seed {}: {:?}", seed, game.cause());
+        }
+    }
+
+    #[test]
+    fn fills_small_boards() {
+        for size in [4, 6] {
+            for seed in 0..3 {
+                let options = Options {
+                    width: size,
+                    height: size,
+                    head_x: 2,
+                    head_y: 0,
+                    self_play: true,
+                    seed: Some(seed),
+                    ..Options::default()
+                };
+                let game = self_play(&options, 10_000);
+
+                assert!(game.is_won(), "{0}x{0} seed {1}", size, seed);
+                assert!(game.cause().is_none());
+            }
+        }
+    }
+}
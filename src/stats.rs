@@ -0,0 +1,364 @@
+use crate::game::{Game, Options};
+use crate::mode::Mode;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Best runs listed in the summary.
+const BEST_RUNS: usize = 5;
+/// Days listed in the summary, the most recent ones with games.
+const DAYS: usize = 7;
+
+/// Where a game is played from.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum Source {
+    #[default]
+    Play,
+    Campaign,
+    /// Trying out a level in the editor, which isn't recorded.
+    TestPlay,
+}
+
+/// A finished game in the history.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameRecord {
+    /// Seconds since the Unix epoch when the game ended.
+    pub time: u64,
+    pub mode: Mode,
+    pub width: i32,
+    pub height: i32,
+    pub speed: f64,
+    /// Name of the level the game was played on.
+    pub level: Option<String>,
+    pub self_play: bool,
+    pub bot: bool,
+    /// Set for a level of the campaign.
+    #[serde(default)]
+    pub campaign: bool,
+    pub rivals: usize,
+    pub seed: u64,
+    /// Set if the game was rewound.
+    #[serde(default)]
+    pub assisted: bool,
+    pub score: usize,
+    pub length: usize,
+    pub ticks: u64,
+    /// Seconds of play.
+    pub duration: f64,
+    /// How the game ended.
+    pub cause: String,
+}
+
+impl GameRecord {
+    pub fn new(game: &Game, options: &Options) -> Self {
+        GameRecord {
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |time| time.as_secs()),
+            mode: game.mode,
+            width: game.board.0,
+            height: game.board.1,
+            speed: options.speed,
+            level: options.level.as_ref().map(|level| level.name.clone()),
+            self_play: game.self_play,
+            bot: options.bot.is_some(),
+            campaign: options.source == Source::Campaign,
+            rivals: game.snakes.len() - 1,
+            seed: game.seed,
+            assisted: game.assisted,
+            score: game.score(),
+            length: game.snake().len(),
+            ticks: game.ticks,
+            duration: game.ticks as f64 / options.speed,
+            cause: cause(game).to_string(),
+        }
+    }
+}
+
+fn cause(game: &Game) -> &'static str {
+    match game.cause() {
+        Some(cause) => cause.name(),
+        None if game.is_won() => "won",
+        None if game.is_completed() => "completed",
+        None => "left",
+    }
+}
+
+/// Every finished game, stored in the user's data directory with a line for
+/// each game.
+pub struct History;
+
+impl History {
+    pub fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("snake").join("history.jsonl"))
+    }
+
+    /// Reads the history, skipping lines that can't be read.
+    pub fn load() -> Vec<GameRecord> {
+        History::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|text| {
+                text.lines()
+                    .filter_map(|line| serde_json::from_str(line).ok())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn append(record: &GameRecord) -> Result<(), Box<dyn Error>> {
+        let path = History::path().ok_or("no data directory")?;
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{}", serde_json::to_string(record)?)?;
+
+        Ok(())
+    }
+
+    /// Replaces the last game in the history with the record.
+    pub fn replace_last(record: &GameRecord) -> Result<(), Box<dyn Error>> {
+        let path = History::path().ok_or("no data directory")?;
+        let text = fs::read_to_string(&path).unwrap_or_default();
+        let kept = text
+            .trim_end()
+            .rsplit_once('\n')
+            .map_or("", |(kept, _)| kept);
+
+        let mut text = String::new();
+        if !kept.is_empty() {
+            text.push_str(kept);
+            text.push('\n');
+        }
+        text.push_str(&serde_json::to_string(record)?);
+        text.push('\n');
+        fs::write(path, text)?;
+
+        Ok(())
+    }
+}
+
+/// Adds the game to the history, or replaces the last game in it with this
+/// one if `replace` is set, for a run that was rewound after it ended. Games
+/// played to test a level are left out.
+pub(crate) fn record_game(game: &Game, options: &Options, replace: bool) {
+    if options.source == Source::TestPlay {
+        return;
+    }
+
+    let record = GameRecord::new(game, options);
+
+    // losing a game from the history isn't worth interrupting the game for
+    let _ = if replace {
+        History::replace_last(&record)
+    } else {
+        History::append(&record)
+    };
+}
+
+/// Formats a Unix time as a `YYYY-MM-DD` date in UTC.
+pub fn date(time: u64) -> String {
+    // civil_from_days by Howard Hinnant
+    let days = (time / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+pub(crate) fn format_duration(seconds: f64) -> String {
+    let seconds = seconds.round() as u64;
+
+    if seconds >= 3600 {
+        format!("{}h {:02}m", seconds / 3600, seconds / 60 % 60)
+    } else {
+        format!("{}m {:02}s", seconds / 60, seconds % 60)
+    }
+}
+
+/// Totals, averages, best runs, games per day and how games ended, as lines of
+/// text.
+pub fn summary(records: &[GameRecord]) -> Vec<String> {
+    if records.is_empty() {
+        return vec!["No games played yet".to_string()];
+    }
+
+    let games = records.len() as f64;
+    let total_time: f64 = records.iter().map(|record| record.duration).sum();
+    let average = |value: fn(&GameRecord) -> f64| records.iter().map(value).sum::<f64>() / games;
+
+    let mut lines = vec![
+        format!("Games played      {}", records.len()),
+        format!("Time played       {}", format_duration(total_time)),
+        format!(
+            "Average score     {:.1}",
+            average(|record| record.score as f64)
+        ),
+        format!(
+            "Average length    {:.1}",
+            average(|record| record.length as f64)
+        ),
+        format!("Average duration  {}", format_duration(total_time / games)),
+        String::new(),
+        "Best runs".to_string(),
+    ];
+
+    let mut best: Vec<&GameRecord> = records.iter().collect();
+    best.sort_by(|a, b| b.score.cmp(&a.score).then(a.time.cmp(&b.time)));
+    for (rank, record) in best.iter().take(BEST_RUNS).enumerate() {
+        lines.push(format!(
+            "  {}. {:>5}{} {:<12} {}  length {}",
+            rank + 1,
+            record.score,
+            if record.assisted { "*" } else { " " },
+            record.mode.name(),
+            date(record.time),
+            record.length
+        ));
+    }
+
+    let mut days: BTreeMap<String, usize> = BTreeMap::new();
+    for record in records {
+        *days.entry(date(record.time)).or_default() += 1;
+    }
+
+    lines.push(String::new());
+    lines.push("Games per day".to_string());
+    for (day, count) in days.iter().rev().take(DAYS) {
+        lines.push(format!("  {}  {:>4}", day, count));
+    }
+
+    let mut causes: BTreeMap<&str, usize> = BTreeMap::new();
+    for record in records {
+        *causes.entry(record.cause.as_str()).or_default() += 1;
+    }
+
+    let mut causes: Vec<(&str, usize)> = causes.into_iter().collect();
+    causes.sort_by_key(|&(_, count)| Reverse(count));
+
+    lines.push(String::new());
+    lines.push("How games ended".to_string());
+    for (cause, count) in causes {
+        lines.push(format!(
+            "  {:<14}{:>5}  {:>3.0}%",
+            cause,
+            count,
+            count as f64 * 100.0 / games
+        ));
+    }
+
+    lines
+}
+
+pub enum Export {
+    Csv,
+    Json,
+}
+
+impl From<&String> for Export {
+    fn from(format: &String) -> Self {
+        match format.as_str() {
+            "csv" => Export::Csv,
+            "json" => Export::Json,
+            _ => panic!(),
+        }
+    }
+}
+
+fn csv_field(field: String) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+/// Prints the summary of the history, or the whole history in the export
+/// format.
+pub fn run(export: Option<Export>) -> Result<(), Box<dyn Error>> {
+    let records = History::load();
+
+    match export {
+        None => {
+            for line in summary(&records) {
+                println!("{}", line);
+            }
+        }
+        Some(Export::Json) => println!("{}", serde_json::to_string_pretty(&records)?),
+        Some(Export::Csv) => {
+            println!(
+                "time,date,mode,width,height,speed,level,self_play,bot,campaign,rivals,seed,assisted,\
+                 score,length,ticks,duration,cause"
+            );
+
+            for record in records {
+                let fields = [
+                    record.time.to_string(),
+                    date(record.time),
+                    record.mode.name().to_string(),
+                    record.width.to_string(),
+                    record.height.to_string(),
+                    record.speed.to_string(),
+                    record.level.unwrap_or_default(),
+                    record.self_play.to_string(),
+                    record.bot.to_string(),
+                    record.campaign.to_string(),
+                    record.rivals.to_string(),
+                    record.seed.to_string(),
+                    record.assisted.to_string(),
+                    record.score.to_string(),
+                    record.length.to_string(),
+                    record.ticks.to_string(),
+                    format!("{:.2}", record.duration),
+                    record.cause,
+                ];
+                let fields: Vec<String> = fields.into_iter().map(csv_field).collect();
+                println!("{}", fields.join(","));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn campaign_games_are_tagged() {
+        let mut options = Options {
+            seed: Some(1),
+            ..Options::default()
+        };
+        let game = Game::new(&options);
+        assert!(!GameRecord::new(&game, &options).campaign);
+
+        options.source = Source::Campaign;
+        let record = GameRecord::new(&game, &options);
+        assert!(record.campaign);
+
+        // records written before the tag read as played outside the campaign
+        let mut line = serde_json::to_value(&record).unwrap();
+        line.as_object_mut().unwrap().remove("campaign");
+        let old: GameRecord = serde_json::from_value(line).unwrap();
+        assert!(!old.campaign);
+    }
+}
@@ -0,0 +1,378 @@
+use crate::bot::Bot;
+use crate::game::{Direction, Game, Options};
+use crate::solver::{PathAlgorithm, Solver};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::time::{Duration, Instant};
+
+/// Tournament description read from a TOML file.
+///
+/// ```toml
+/// seeds = [1, 2, 3]
+/// max_ticks = 20000
+///
+/// [[boards]]
+/// width = 10
+/// height = 10
+///
+/// [[bots]]
+/// name = "hamilton"
+/// solver = "bfs"
+///
+/// [[bots]]
+/// name = "greedy"
+/// command = "python3 greedy.py"
+/// timeout = 50
+/// ```
+#[derive(Deserialize)]
+struct Config {
+    seeds: Vec<u64>,
+    #[serde(default = "default_max_ticks")]
+    max_ticks: u64,
+    boards: Vec<BoardConfig>,
+    bots: Vec<BotConfig>,
+}
+
+fn default_max_ticks() -> u64 {
+    20_000
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct BoardConfig {
+    width: i32,
+    height: i32,
+    head_x: i32,
+    head_y: i32,
+    length: i32,
+    direction: Direction,
+    borders: bool,
+}
+
+impl Default for BoardConfig {
+    fn default() -> Self {
+        let options = Options::default();
+
+        BoardConfig {
+            width: options.width,
+            height: options.height,
+            head_x: options.head_x,
+            head_y: options.head_y,
+            length: options.length,
+            direction: options.direction,
+            borders: options.borders,
+        }
+    }
+}
+
+impl BoardConfig {
+    fn options(&self, kind: &BotKind, seed: u64) -> Options {
+        let mut options = Options {
+            width: self.width,
+            height: self.height,
+            head_x: self.head_x,
+            head_y: self.head_y,
+            length: self.length,
+            direction: self.direction.clone(),
+            borders: self.borders,
+            seed: Some(seed),
+            ..Options::default()
+        };
+
+        if let BotKind::Solver { solver } = kind {
+            options.self_play = true;
+            options.path_alg = solver.clone();
+        }
+
+        options
+    }
+
+    fn name(&self) -> String {
+        format!("{}x{}", self.width, self.height)
+    }
+}
+
+#[derive(Deserialize)]
+struct BotConfig {
+    name: String,
+    #[serde(flatten)]
+    kind: BotKind,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BotKind {
+    Solver {
+        solver: PathAlgorithm,
+    },
+    Command {
+        command: String,
+        #[serde(default = "default_timeout")]
+        timeout: u64,
+    },
+}
+
+fn default_timeout() -> u64 {
+    Options::default().bot_timeout
+}
+
+enum Player {
+    Solver(Box<Solver>),
+    Bot(Box<Bot>),
+}
+
+impl Player {
+    fn new(kind: &BotKind, game: &Game) -> Result<Self, Box<dyn Error>> {
+        Ok(match kind {
+            BotKind::Solver { .. } => Player::Solver(Box::new(Solver::new(game))),
+            BotKind::Command { command, timeout } => Player::Bot(Box::new(Bot::spawn(
+                command,
+                Duration::from_millis(*timeout),
+            )?)),
+        })
+    }
+
+    fn next_direction(&mut self, game: &Game) -> Direction {
+        match self {
+            Player::Solver(solver) => solver.next_direction(game),
+            Player::Bot(bot) => bot.next_direction(game),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct GameResult {
+    board: String,
+    seed: u64,
+    score: usize,
+    ticks: u64,
+    won: bool,
+    time_ms: f64,
+}
+
+#[derive(Serialize)]
+struct Standing {
+    rank: usize,
+    name: String,
+    total_score: usize,
+    average_score: f64,
+    wins: usize,
+    win_rate: f64,
+    average_ticks: f64,
+    average_move_us: f64,
+    games: Vec<GameResult>,
+}
+
+pub enum Format {
+    Table,
+    Json,
+}
+
+impl From<&String> for Format {
+    fn from(format: &String) -> Self {
+        match format.as_str() {
+            "table" => Format::Table,
+            "json" => Format::Json,
+            _ => panic!(),
+        }
+    }
+}
+
+pub fn run(path: &str, format: Format) -> Result<(), Box<dyn Error>> {
+    let config: Config = toml::from_str(&fs::read_to_string(path)?)?;
+    let standings = standings(&config)?;
+
+    match format {
+        Format::Table => print_table(&standings),
+        Format::Json => println!("{}", serde_json::to_string_pretty(&standings)?),
+    }
+
+    Ok(())
+}
+
+/// Plays every bot on every board and seed and ranks the bots.
+fn standings(config: &Config) -> Result<Vec<Standing>, Box<dyn Error>> {
+    for board in &config.boards {
+        for bot in &config.bots {
+            Game::check_options(&board.options(&bot.kind, 0))
+                .map_err(|err| format!("board {} for bot {}: {}", board.name(), bot.name, err))?;
+        }
+    }
+
+    let mut standings = Vec::new();
+
+    for bot in &config.bots {
+        let mut games = Vec::new();
+
+        for board in &config.boards {
+            for seed in &config.seeds {
+                games.push(play(config, board, &bot.kind, *seed)?);
+            }
+        }
+
+        standings.push(standing(&bot.name, games));
+    }
+
+    standings.sort_by(|a, b| {
+        b.total_score
+            .cmp(&a.total_score)
+            .then(b.wins.cmp(&a.wins))
+            .then(a.average_ticks.total_cmp(&b.average_ticks))
+    });
+
+    for (idx, standing) in standings.iter_mut().enumerate() {
+        standing.rank = idx + 1;
+    }
+
+    Ok(standings)
+}
+
+fn play(
+    config: &Config,
+    board: &BoardConfig,
+    kind: &BotKind,
+    seed: u64,
+) -> Result<GameResult, Box<dyn Error>> {
+    let options = board.options(kind, seed);
+    let mut game = Game::new(&options);
+    let mut player = Player::new(kind, &game)?;
+    let mut time = Duration::ZERO;
+
+    while game.is_running() && game.ticks < config.max_ticks {
+        let start = Instant::now();
+        let dir = player.next_direction(&game);
+        time += start.elapsed();
+
+        game.move_snake(dir);
+    }
+
+    Ok(GameResult {
+        board: board.name(),
+        seed,
+        score: game.score(),
+        ticks: game.ticks,
+        won: game.is_won(),
+        time_ms: time.as_secs_f64() * 1000.0,
+    })
+}
+
+fn standing(name: &str, games: Vec<GameResult>) -> Standing {
+    let count = games.len().max(1) as f64;
+    let total_score = games.iter().map(|game| game.score).sum();
+    let wins = games.iter().filter(|game| game.won).count();
+    let ticks: u64 = games.iter().map(|game| game.ticks).sum();
+    let time_ms: f64 = games.iter().map(|game| game.time_ms).sum();
+
+    Standing {
+        rank: 0,
+        name: name.to_string(),
+        total_score,
+        average_score: total_score as f64 / count,
+        wins,
+        win_rate: wins as f64 / count,
+        average_ticks: ticks as f64 / count,
+        average_move_us: time_ms * 1000.0 / ticks.max(1) as f64,
+        games,
+    }
+}
+
+fn print_table(standings: &[Standing]) {
+    let name_width = standings
+        .iter()
+        .map(|standing| standing.name.len())
+        .max()
+        .unwrap_or(0)
+        .max(3);
+
+    println!(
+        "{:>4}  {:<name_width$}  {:>7}  {:>9}  {:>8}  {:>9}  {:>12}",
+        "Rank", "Bot", "Score", "Avg score", "Win rate", "Avg ticks", "Avg move us"
+    );
+
+    for standing in standings {
+        println!(
+            "{:>4}  {:<name_width$}  {:>7}  {:>9.1}  {:>7.1}%  {:>9.1}  {:>12.1}",
+            standing.rank,
+            standing.name,
+            standing.total_score,
+            standing.average_score,
+            standing.win_rate * 100.0,
+            standing.average_ticks,
+            standing.average_move_us,
+        );
+    }
+
+    for standing in standings {
+        println!("\n{}. {}", standing.rank, standing.name);
+        println!(
+            "  {:<9}  {:>10}  {:>7}  {:>8}  {:>6}  {:>10}",
+            "Board", "Seed", "Score", "Ticks", "Result", "Time ms"
+        );
+
+        for game in &standing.games {
+            println!(
+                "  {:<9}  {:>10}  {:>7}  {:>8}  {:>6}  {:>10.2}",
+                game.board,
+                game.seed,
+                game.score,
+                game.ticks,
+                if game.won { "won" } else { "lost" },
+                game.time_ms,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    const CONFIG: &str = r#"
+seeds = [1, 2, 3]
+max_ticks = 3000
+
+[[boards]]
+width = 6
+height = 6
+head_x = 2
+head_y = 0
+
+[[boards]]
+width = 12
+height = 8
+
+[[bots]]
+name = "bfs"
+solver = "bfs"
+
+[[bots]]
+name = "astar"
+solver = "astar"
+"#;
+
+    /// The standings as JSON without the timings.
+    fn outcome(standings: &[Standing]) -> Value {
+        let mut value = serde_json::to_value(standings).unwrap();
+
+        for standing in value.as_array_mut().unwrap() {
+            standing["average_move_us"] = Value::Null;
+            for game in standing["games"].as_array_mut().unwrap() {
+                game["time_ms"] = Value::Null;
+            }
+        }
+
+        value
+    }
+
+    #[test]
+    fn same_config_gives_same_standings() {
+        let config: Config = toml::from_str(CONFIG).unwrap();
+        let first = outcome(&standings(&config).unwrap());
+
+        assert_eq!(first.as_array().unwrap().len(), 2);
+        assert!(first[0]["total_score"].as_u64() > Some(0));
+        assert_eq!(first, outcome(&standings(&config).unwrap()));
+    }
+}
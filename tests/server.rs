@@ -0,0 +1,115 @@
+//! Plays a round on a server over loopback with two scripted clients.
+
+use snake::game::Options;
+use snake::net::{self, ClientMessage, ServerMessage};
+use snake::server::Server;
+use std::io::{BufRead, BufReader, Lines};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+struct TestClient {
+    stream: TcpStream,
+    lines: Lines<BufReader<TcpStream>>,
+}
+
+impl TestClient {
+    fn connect(listener: &TcpListener, name: &str) -> Self {
+        let mut stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(10)))
+            .unwrap();
+        let lines = BufReader::new(stream.try_clone().unwrap()).lines();
+
+        let hello = ClientMessage::Hello {
+            name: name.to_string(),
+        };
+        net::send(&mut stream, &hello).unwrap();
+
+        TestClient { stream, lines }
+    }
+
+    fn ready(&mut self) {
+        net::send(&mut self.stream, &ClientMessage::Ready { ready: true }).unwrap();
+    }
+
+    fn next(&mut self) -> ServerMessage {
+        let line = self.lines.next().expect("the server hung up").unwrap();
+        serde_json::from_str(&line).unwrap()
+    }
+
+    /// Skips messages until one `f` accepts.
+    fn next_matching<T>(&mut self, mut f: impl FnMut(ServerMessage) -> Option<T>) -> T {
+        loop {
+            if let Some(value) = f(self.next()) {
+                return value;
+            }
+        }
+    }
+}
+
+#[test]
+fn two_clients_play_a_round_on_one_board() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let options = Options {
+        width: 10,
+        height: 10,
+        speed: 100.0,
+        seed: Some(0),
+        ..Options::default()
+    };
+    let server = Server::new(options, 2);
+    let server_listener = listener.try_clone().unwrap();
+    thread::spawn(move || server.serve(server_listener).unwrap());
+
+    let mut alice = TestClient::connect(&listener, "alice");
+    let mut bob = TestClient::connect(&listener, "bob");
+
+    let mut ids = Vec::new();
+    for client in [&mut alice, &mut bob] {
+        match client.next() {
+            ServerMessage::Welcome { id, width, height } => {
+                assert_eq!((width, height), (10, 10));
+                ids.push(id);
+            }
+            _ => panic!("expected a welcome"),
+        }
+        assert!(matches!(client.next(), ServerMessage::Lobby { .. }));
+    }
+    assert_ne!(ids[0], ids[1]);
+
+    alice.ready();
+    bob.ready();
+
+    for client in [&mut alice, &mut bob] {
+        let players = client.next_matching(|message| match message {
+            ServerMessage::State { players, .. } => Some(players),
+            _ => None,
+        });
+
+        // both snakes are on the same board, eating the same apple
+        assert_eq!(players.len(), 2);
+        assert_eq!(players[0].apple, players[1].apple);
+        assert!(players.iter().all(|player| player.alive));
+        assert!(players[0]
+            .snake
+            .iter()
+            .all(|p| !players[1].snake.contains(p)));
+        let mut names: Vec<&str> = players.iter().map(|p| p.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, ["alice", "bob"]);
+    }
+
+    // nobody steers, so both snakes run into a border and the round ends
+    for client in [&mut alice, &mut bob] {
+        let lobby = client.next_matching(|message| match message {
+            ServerMessage::Lobby { players } => Some(players),
+            _ => None,
+        });
+
+        assert_eq!(lobby.len(), 2);
+        assert!(lobby
+            .iter()
+            .all(|player| !player.ready && player.score.is_some()));
+    }
+}